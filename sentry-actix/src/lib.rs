@@ -34,6 +34,12 @@
 //! }
 //! ```
 //!
+//! # Release Health
+//!
+//! When `auto_session_tracking` is enabled and the `session_mode` is set to
+//! `SessionMode::Request` in the client options, the middleware starts a
+//! session for every request.  These sessions are sent in aggregated form.
+//!
 //! # Reusing the Hub
 //!
 //! If you use this integration the `Hub::current()` returned hub is typically the wrong one.
@@ -72,7 +78,7 @@ use actix_web::{Error, HttpMessage, HttpRequest, HttpResponse};
 use failure::Fail;
 use sentry::internals::{ScopeGuard, Uuid};
 use sentry::protocol::{ClientSdkPackage, Event, Level};
-use sentry::Hub;
+use sentry::{Hub, SessionMode};
 use sentry_failure::exception_from_single_fail;
 
 /// A helper construct that can be used to reconfigure and build the middleware.
@@ -201,11 +207,18 @@ impl<S: 'static> Middleware<S> for SentryMiddleware {
         let outer_req = req;
         let req = outer_req.clone();
         let client = hub.client();
+        let track_sessions = client.as_ref().map_or(false, |client| {
+            let options = client.options();
+            options.auto_session_tracking && options.session_mode == SessionMode::Request
+        });
 
         let req = fragile::SemiSticky::new(req);
         let cached_data = Arc::new(Mutex::new(None));
 
         let root_scope = hub.push_scope();
+        if track_sessions {
+            hub.start_session();
+        }
         hub.configure_scope(move |scope| {
             scope.add_event_processor(Box::new(move |mut event| {
                 let mut cached_data = cached_data.lock().unwrap();
@@ -271,6 +284,7 @@ impl<S: 'static> Middleware<S> for SentryMiddleware {
         // on the scope which in turn will release the circular dependency we have
         // with the hub via the request.
         if let Some(hub_wrapper) = req.extensions().get::<HubWrapper>() {
+            hub_wrapper.hub.end_session();
             if let Ok(mut guard) = hub_wrapper.root_scope.try_borrow_mut() {
                 guard.take();
             }
@@ -1,7 +1,9 @@
+use std::time::Duration;
+
 use crate::breadcrumbs::IntoBreadcrumbs;
-#[cfg(feature = "with_client_implementation")]
 use crate::hub::Hub;
 use crate::internals;
+use crate::performance::{Transaction, TransactionContext};
 use crate::protocol::{Event, Level};
 use crate::scope::Scope;
 
@@ -165,6 +167,74 @@ where
     }
 }
 
+/// Starts a new performance monitoring transaction.
+///
+/// Whether the transaction is sent to Sentry is decided on start by the
+/// `traces_sampler` or the `traces_sample_rate` of the client.  The
+/// transaction has to be finished explicitly to be sent.  To link events and
+/// child spans to it, it can be set as the active span on the scope.
+///
+/// # Example
+///
+/// ```
+/// # use sentry_core as sentry;
+/// use sentry::{start_transaction, TransactionContext};
+///
+/// let ctx = TransactionContext::new("process batch", "task");
+/// let transaction = start_transaction(ctx);
+/// sentry::configure_scope(|scope| scope.set_span(Some(transaction.clone().into())));
+///
+/// let span = transaction.start_child("db", "SELECT * FROM jobs");
+/// span.finish();
+///
+/// transaction.finish();
+/// ```
+pub fn start_transaction(ctx: TransactionContext) -> Transaction {
+    #[cfg(feature = "with_client_implementation")]
+    {
+        Hub::with(|hub| hub.start_transaction(ctx))
+    }
+    #[cfg(not(feature = "with_client_implementation"))]
+    {
+        Transaction::new_noop(ctx)
+    }
+}
+
+/// Starts a new release health session on the current hub.
+///
+/// See [`Hub::start_session`](struct.Hub.html#method.start_session).
+pub fn start_session() {
+    Hub::with_active(|hub| hub.start_session())
+}
+
+/// Ends the release health session of the current hub.
+///
+/// See [`Hub::end_session`](struct.Hub.html#method.end_session).
+pub fn end_session() {
+    Hub::with_active(|hub| hub.end_session())
+}
+
+/// Waits until the events queued on the current client have been sent.
+///
+/// Unlike dropping the guard returned by `sentry::init` this leaves the
+/// client usable, which is useful at the end of short lived jobs or
+/// serverless handlers.  If no timeout is provided the `shutdown_timeout`
+/// of the client options is used.  Returns `false` if the timeout elapsed
+/// before the queue was drained.
+///
+/// See [`Hub::flush`](struct.Hub.html#method.flush).
+pub fn flush(timeout: Option<Duration>) -> bool {
+    #[cfg(feature = "with_client_implementation")]
+    {
+        Hub::with(|hub| hub.flush(timeout))
+    }
+    #[cfg(not(feature = "with_client_implementation"))]
+    {
+        let _timeout = timeout;
+        true
+    }
+}
+
 /// Returns the last event ID captured.
 pub fn last_event_id() -> Option<internals::Uuid> {
     with_client_impl! {{
@@ -8,12 +8,19 @@ use std::time::Duration;
 
 use rand::random;
 
+use crate::client_reports::{ClientReportFlusher, ClientReportRecorder};
 pub use crate::clientoptions::ClientOptions;
+use crate::clientoptions::SessionMode;
 use crate::constants::SDK_INFO;
 use crate::integrations::Integration;
 use crate::internals::{Dsn, Uuid};
-use crate::protocol::{ClientSdkInfo, Event};
+use crate::performance::TransactionContext;
+use crate::protocol::{
+    ClientSdkInfo, DataCategory, DiscardReason, DiscardedEvent, Envelope, Event, SessionAttributes,
+    SessionUpdate, Transaction,
+};
 use crate::scope::Scope;
+use crate::session::SessionFlusher;
 use crate::transport::Transport;
 
 impl<T: Into<ClientOptions>> From<T> for Client {
@@ -26,6 +33,9 @@ impl<T: Into<ClientOptions>> From<T> for Client {
 pub struct Client {
     options: ClientOptions,
     transport: RwLock<Option<Arc<dyn Transport>>>,
+    session_flusher: RwLock<Option<Arc<SessionFlusher>>>,
+    client_reports: Arc<ClientReportRecorder>,
+    client_report_flusher: RwLock<Option<Arc<ClientReportFlusher>>>,
     integrations: Vec<(TypeId, Arc<dyn Integration>)>,
     sdk_info: ClientSdkInfo,
 }
@@ -41,7 +51,12 @@ impl fmt::Debug for Client {
 
 impl Clone for Client {
     fn clone(&self) -> Client {
+        // the flushers are shared, they flush for the last time once all
+        // clones dropped them
         Client {
+            session_flusher: RwLock::new(self.session_flusher.read().unwrap().clone()),
+            client_report_flusher: RwLock::new(self.client_report_flusher.read().unwrap().clone()),
+            client_reports: self.client_reports.clone(),
             options: self.options.clone(),
             transport: RwLock::new(self.transport.read().unwrap().clone()),
             integrations: self.integrations.clone(),
@@ -86,7 +101,18 @@ impl Client {
             Some(factory.create_transport(&options))
         };
 
-        let transport = RwLock::new(create_transport());
+        let transport = create_transport();
+        let session_flusher = RwLock::new(create_session_flusher(&options, &transport));
+        let client_reports = transport
+            .as_ref()
+            .and_then(|transport| transport.client_reports())
+            .unwrap_or_default();
+        let client_report_flusher = RwLock::new(create_client_report_flusher(
+            &options,
+            &transport,
+            &client_reports,
+        ));
+        let transport = RwLock::new(transport);
 
         let mut sdk_info = SDK_INFO.clone();
 
@@ -106,6 +132,9 @@ impl Client {
         Client {
             options,
             transport,
+            session_flusher,
+            client_reports,
+            client_report_flusher,
             integrations,
             sdk_info,
         }
@@ -124,7 +153,7 @@ impl Client {
         &self,
         mut event: Event<'static>,
         scope: Option<&Scope>,
-    ) -> Option<Event<'static>> {
+    ) -> Result<(Event<'static>, Option<f32>), DiscardReason> {
         // event_id and sdk_info are set before the processors run so that the
         // processors can poke around in that data.
         if event.event_id.is_nil() {
@@ -139,7 +168,7 @@ impl Client {
         if let Some(scope) = scope {
             event = match scope.apply_to_event(event) {
                 Some(event) => event,
-                None => return None,
+                None => return Err(DiscardReason::EventProcessor),
             };
         }
 
@@ -149,11 +178,16 @@ impl Client {
                 Some(event) => event,
                 None => {
                     sentry_debug!("integration dropped event {:?}", id);
-                    return None;
+                    return Err(DiscardReason::EventProcessor);
                 }
             }
         }
 
+        if self.is_error_ignored(&event) {
+            sentry_debug!("ignore_errors dropped event {:?}", event.event_id);
+            return Err(DiscardReason::Filtered);
+        }
+
         if event.release.is_none() {
             event.release = self.options.release.clone();
         }
@@ -167,16 +201,50 @@ impl Client {
             event.platform = "native".into();
         }
 
-        if let Some(ref func) = self.options.before_send {
+        let sample_rate = match self.options.sampler {
+            Some(ref sampler) => {
+                let rate = sampler(&event);
+                if !sample_should_send(rate) {
+                    sentry_debug!("sampler dropped event {:?}", event.event_id);
+                    return Err(DiscardReason::SampleRate);
+                }
+                Some(rate)
+            }
+            None => None,
+        };
+
+        let event = if let Some(ref func) = self.options.before_send {
             sentry_debug!("invoking before_send callback");
             let id = event.event_id;
-            func(event).or_else(move || {
+            func(event).ok_or_else(move || {
                 sentry_debug!("before_send dropped event {:?}", id);
-                None
-            })
+                DiscardReason::BeforeSend
+            })?
         } else {
-            Some(event)
+            event
+        };
+        Ok((event, sample_rate))
+    }
+
+    /// Checks the event against the `ignore_errors` patterns.
+    fn is_error_ignored(&self, event: &Event<'static>) -> bool {
+        let patterns = &self.options.ignore_errors;
+        if patterns.is_empty() {
+            return false;
         }
+        let exception_strings = event.exception.iter().flat_map(|exc| {
+            Some(exc.ty.as_str())
+                .into_iter()
+                .chain(exc.value.as_deref())
+        });
+        let message_strings = event
+            .message
+            .as_deref()
+            .into_iter()
+            .chain(event.logentry.as_ref().map(|entry| entry.message.as_str()));
+        exception_strings
+            .chain(message_strings)
+            .any(|value| patterns.iter().any(|pattern| pattern.is_match(value)))
     }
 
     /// Returns the options of this client.
@@ -197,17 +265,136 @@ impl Client {
     /// Captures an event and sends it to sentry.
     pub fn capture_event(&self, event: Event<'static>, scope: Option<&Scope>) -> Uuid {
         if let Some(ref transport) = *self.transport.read().unwrap() {
-            if self.sample_should_send() {
-                if let Some(event) = self.prepare_event(event, scope) {
+            // with a sampler the decision is deferred until the event is prepared
+            if self.options.sampler.is_none() && !sample_should_send(self.options.sample_rate) {
+                self.record_discard(DiscardReason::SampleRate, DataCategory::Error);
+                return Default::default();
+            }
+            match self.prepare_event(event, scope) {
+                Ok((event, sample_rate)) => {
                     let event_id = event.event_id;
-                    transport.send_event(event);
+                    let session_item =
+                        scope.and_then(|scope| scope.update_session_from_event(&event));
+                    let mut envelope: Envelope = event.into();
+                    envelope.headers_mut().sample_rate = sample_rate;
+                    if let Some(item) = session_item {
+                        envelope.add_item(item);
+                    }
+                    if let Some(scope) = scope {
+                        for attachment in scope.attachments.iter() {
+                            match attachment.load() {
+                                Ok(attachment) => envelope.add_item(attachment),
+                                Err(err) => {
+                                    sentry_debug!(
+                                        "failed to read attachment {}: {}",
+                                        attachment.filename,
+                                        err
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    transport.send_envelope(envelope);
                     return event_id;
                 }
+                Err(reason) => self.record_discard(reason, DataCategory::Error),
             }
         }
         Default::default()
     }
 
+    /// Sends the envelope to sentry.
+    ///
+    /// The envelope is handed to the transport as is, without any further
+    /// processing or sampling.
+    pub fn send_envelope(&self, envelope: Envelope) {
+        if let Some(ref transport) = *self.transport.read().unwrap() {
+            transport.send_envelope(envelope);
+        }
+    }
+
+    /// Returns how many items were discarded instead of being sent, by
+    /// reason and data category.
+    ///
+    /// This includes the data dropped by the transport if it keeps track of
+    /// it.  The counts are not reset when client reports are sent.
+    pub fn discarded_events(&self) -> Vec<DiscardedEvent> {
+        self.client_reports.discarded_events()
+    }
+
+    /// Records a discarded item for the client reports.
+    pub(crate) fn record_discard(&self, reason: DiscardReason, category: DataCategory) {
+        self.client_reports.record(reason, category, 1);
+    }
+
+    /// Counts a closed request-mode session for the next aggregate.
+    pub(crate) fn enqueue_session(&self, session_update: &SessionUpdate<'static>) {
+        if let Some(ref flusher) = *self.session_flusher.read().unwrap() {
+            flusher.enqueue(session_update);
+        }
+    }
+
+    /// Fills in the client defaults and sends a finished transaction.
+    pub(crate) fn capture_transaction(&self, mut transaction: Transaction<'static>) {
+        if transaction.sdk.is_none() {
+            transaction.sdk = Some(Cow::Owned(self.sdk_info.clone()));
+        }
+        if transaction.release.is_none() {
+            transaction.release = self.options.release.clone();
+        }
+        if transaction.environment.is_none() {
+            transaction.environment = self.options.environment.clone();
+        }
+        self.send_envelope(transaction.into());
+    }
+
+    /// Checks the name of a new transaction against the `ignore_transactions`
+    /// patterns.
+    pub(crate) fn is_transaction_ignored(&self, ctx: &TransactionContext) -> bool {
+        self.options
+            .ignore_transactions
+            .iter()
+            .any(|pattern| pattern.is_match(ctx.name()))
+    }
+
+    /// Decides whether a new transaction should be sent to sentry.
+    ///
+    /// An explicit sampling decision on the context wins, otherwise the
+    /// `traces_sampler` or the `traces_sample_rate` are consulted.
+    pub(crate) fn is_transaction_sampled(&self, ctx: &TransactionContext) -> bool {
+        if let Some(sampled) = ctx.sampled() {
+            return sampled;
+        }
+        let rate = match self.options.traces_sampler {
+            Some(ref sampler) => sampler(ctx),
+            None => self.options.traces_sample_rate,
+        };
+        sample_should_send(rate)
+    }
+
+    /// Drains all pending events without shutting down the transport.
+    ///
+    /// Pending sessions and client reports are handed to the transport
+    /// first.  This returns `true` if the queue was successfully drained in
+    /// the given time or `false` if not.  If no timeout is provided the
+    /// client will wait for as long a `shutdown_timeout` in the client
+    /// options.
+    pub fn flush(&self, timeout: Option<Duration>) -> bool {
+        if let Some(ref flusher) = *self.session_flusher.read().unwrap() {
+            flusher.flush();
+        }
+        if let Some(ref flusher) = *self.client_report_flusher.read().unwrap() {
+            flusher.flush();
+        }
+        if let Some(ref transport) = *self.transport.read().unwrap() {
+            sentry_debug!("client flush; request transport to flush");
+            transport.flush(timeout.unwrap_or(self.options.shutdown_timeout))
+        } else {
+            sentry_debug!("client flush; no transport to flush");
+            true
+        }
+    }
+
     /// Drains all pending events and shuts down the transport behind the
     /// client.  After shutting down the transport is removed.
     ///
@@ -216,6 +403,10 @@ impl Client {
     /// If no timeout is provided the client will wait for as long a
     /// `shutdown_timeout` in the client options.
     pub fn close(&self, timeout: Option<Duration>) -> bool {
+        // dropping the last reference to a flusher hands the remaining
+        // sessions and reports to the transport
+        drop(self.session_flusher.write().unwrap().take());
+        drop(self.client_report_flusher.write().unwrap().take());
         if let Some(transport) = self.transport.write().unwrap().take() {
             sentry_debug!("client close; request transport to shut down");
             transport.shutdown(timeout.unwrap_or(self.options.shutdown_timeout))
@@ -224,14 +415,48 @@ impl Client {
             true
         }
     }
+}
 
-    fn sample_should_send(&self) -> bool {
-        let rate = self.options.sample_rate;
-        if rate >= 1.0 {
-            true
-        } else {
-            random::<f32>() <= rate
-        }
+fn create_session_flusher(
+    options: &ClientOptions,
+    transport: &Option<Arc<dyn Transport>>,
+) -> Option<Arc<SessionFlusher>> {
+    if options.session_mode != SessionMode::Request {
+        return None;
+    }
+    let attributes = SessionAttributes {
+        release: options.release.clone()?,
+        environment: options.environment.clone(),
+        ip_address: None,
+        user_agent: None,
+    };
+    Some(Arc::new(SessionFlusher::new(
+        transport.clone()?,
+        attributes,
+    )))
+}
+
+fn create_client_report_flusher(
+    options: &ClientOptions,
+    transport: &Option<Arc<dyn Transport>>,
+    client_reports: &Arc<ClientReportRecorder>,
+) -> Option<Arc<ClientReportFlusher>> {
+    if !options.send_client_reports {
+        return None;
+    }
+    Some(Arc::new(ClientReportFlusher::new(
+        transport.clone()?,
+        client_reports.clone(),
+    )))
+}
+
+fn sample_should_send(rate: f32) -> bool {
+    if rate >= 1.0 {
+        true
+    } else if rate <= 0.0 {
+        false
+    } else {
+        random::<f32>() <= rate
     }
 }
 
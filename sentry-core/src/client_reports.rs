@@ -0,0 +1,175 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+#[cfg(feature = "with_client_implementation")]
+use std::{
+    sync::{Arc, Condvar},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use crate::protocol::{
+    ClientReport, DataCategory, DiscardReason, DiscardedEvent, Envelope, EnvelopeItem,
+};
+#[cfg(feature = "with_client_implementation")]
+use crate::transport::Transport;
+
+/// The interval in which client reports are sent.
+#[cfg(feature = "with_client_implementation")]
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+type Counters = HashMap<(DiscardReason, DataCategory), u64>;
+
+#[derive(Debug, Default)]
+struct RecorderInner {
+    pending: Counters,
+    totals: Counters,
+}
+
+/// Counts the data that was discarded instead of being sent to Sentry.
+///
+/// The client and its transport record every item they drop together with
+/// the reason it was dropped for.  The counts are periodically sent to
+/// Sentry as client reports and can be inspected locally with
+/// `discarded_events`.
+#[derive(Debug, Default)]
+pub struct ClientReportRecorder {
+    inner: Mutex<RecorderInner>,
+}
+
+impl ClientReportRecorder {
+    /// Creates a new recorder without any counts.
+    pub fn new() -> ClientReportRecorder {
+        ClientReportRecorder::default()
+    }
+
+    /// Records discarded items of a category.
+    pub fn record(&self, reason: DiscardReason, category: DataCategory, quantity: u64) {
+        if quantity == 0 {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        *inner.pending.entry((reason, category)).or_insert(0) += quantity;
+        *inner.totals.entry((reason, category)).or_insert(0) += quantity;
+    }
+
+    /// Records all items of a discarded envelope.
+    ///
+    /// Client reports themselves are never counted.
+    pub fn record_envelope(&self, reason: DiscardReason, envelope: &Envelope) {
+        for item in envelope.items() {
+            if let EnvelopeItem::ClientReport(..) = item {
+                continue;
+            }
+            self.record(reason, item.data_category(), 1);
+        }
+    }
+
+    /// Returns the counts of all data discarded since the recorder was
+    /// created.
+    pub fn discarded_events(&self) -> Vec<DiscardedEvent> {
+        to_discarded_events(&self.inner.lock().unwrap().totals)
+    }
+
+    /// Takes the counts recorded since the last report was taken.
+    ///
+    /// Returns `None` if nothing was discarded in the meantime.
+    pub fn take_report(&self) -> Option<ClientReport> {
+        let pending = std::mem::take(&mut self.inner.lock().unwrap().pending);
+        if pending.is_empty() {
+            return None;
+        }
+        Some(ClientReport {
+            discarded_events: to_discarded_events(&pending),
+            ..Default::default()
+        })
+    }
+}
+
+fn to_discarded_events(counters: &Counters) -> Vec<DiscardedEvent> {
+    let mut discarded_events: Vec<_> = counters
+        .iter()
+        .map(|(&(reason, category), &quantity)| DiscardedEvent {
+            reason,
+            category,
+            quantity,
+        })
+        .collect();
+    discarded_events.sort_by_key(|event| (event.reason, event.category));
+    discarded_events
+}
+
+/// Periodically sends the counts of a recorder as client reports.
+///
+/// A background thread sends a report every 30 seconds if anything was
+/// discarded, the remaining counts are sent when the flusher is dropped.
+#[cfg(feature = "with_client_implementation")]
+pub struct ClientReportFlusher {
+    transport: Arc<dyn Transport>,
+    recorder: Arc<ClientReportRecorder>,
+    shutdown: Arc<(Mutex<bool>, Condvar)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+#[cfg(feature = "with_client_implementation")]
+impl ClientReportFlusher {
+    /// Creates a new flusher sending to the given transport.
+    pub fn new(transport: Arc<dyn Transport>, recorder: Arc<ClientReportRecorder>) -> Self {
+        let shutdown = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let worker = {
+            let transport = transport.clone();
+            let recorder = recorder.clone();
+            let shutdown = shutdown.clone();
+            thread::Builder::new()
+                .name("sentry-client-reports".into())
+                .spawn(move || {
+                    let (lock, cvar) = &*shutdown;
+                    let mut shutdown = lock.lock().unwrap();
+                    let mut last_flush = Instant::now();
+                    while !*shutdown {
+                        let timeout = FLUSH_INTERVAL
+                            .checked_sub(last_flush.elapsed())
+                            .unwrap_or_default();
+                        shutdown = cvar.wait_timeout(shutdown, timeout).unwrap().0;
+                        if last_flush.elapsed() >= FLUSH_INTERVAL {
+                            flush_recorder(&recorder, &*transport);
+                            last_flush = Instant::now();
+                        }
+                    }
+                })
+                .ok()
+        };
+
+        ClientReportFlusher {
+            transport,
+            recorder,
+            shutdown,
+            worker,
+        }
+    }
+
+    /// Sends the current counts immediately.
+    pub fn flush(&self) {
+        flush_recorder(&self.recorder, &*self.transport);
+    }
+}
+
+#[cfg(feature = "with_client_implementation")]
+impl Drop for ClientReportFlusher {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.shutdown;
+        *lock.lock().unwrap() = true;
+        cvar.notify_one();
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+        self.flush();
+    }
+}
+
+#[cfg(feature = "with_client_implementation")]
+fn flush_recorder(recorder: &ClientReportRecorder, transport: &dyn Transport) {
+    if let Some(report) = recorder.take_report() {
+        transport.send_envelope(report.into());
+    }
+}
@@ -1,5 +1,8 @@
 use std::borrow::Cow;
 use std::fmt;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -7,12 +10,257 @@ use crate::constants::USER_AGENT;
 use crate::integrations::Integration;
 use crate::internals::Dsn;
 use crate::intodsn::IntoDsn;
+use crate::performance::TransactionContext;
 use crate::protocol::{Breadcrumb, Event};
 use crate::transport::TransportFactory;
 
 /// Type alias for before event/breadcrumb handlers.
 pub type BeforeCallback<T> = Arc<dyn Fn(T) -> Option<T> + Send + Sync>;
 
+/// Type alias for the callback deciding the sample rate of transactions.
+pub type TracesSampler = Arc<dyn Fn(&TransactionContext) -> f32 + Send + Sync>;
+
+/// Type alias for the callback deciding the sample rate of events.
+pub type EventSampler = Arc<dyn Fn(&Event<'static>) -> f32 + Send + Sync>;
+
+/// A pattern for `ignore_errors` and `ignore_transactions`.
+///
+/// Strings convert into substring patterns.
+#[derive(Clone, Debug)]
+pub enum IgnorePattern {
+    /// Matches strings containing the substring.
+    Substring(Cow<'static, str>),
+    /// Matches strings the regular expression matches.
+    #[cfg(feature = "with_client_implementation")]
+    Regex(regex::Regex),
+}
+
+impl IgnorePattern {
+    /// Creates a pattern from a regular expression.
+    #[cfg(feature = "with_client_implementation")]
+    pub fn regex(pattern: &str) -> Result<IgnorePattern, regex::Error> {
+        regex::Regex::new(pattern).map(IgnorePattern::Regex)
+    }
+
+    /// Checks whether the pattern matches the string.
+    pub fn is_match(&self, value: &str) -> bool {
+        match *self {
+            IgnorePattern::Substring(ref substring) => value.contains(substring.as_ref()),
+            #[cfg(feature = "with_client_implementation")]
+            IgnorePattern::Regex(ref regex) => regex.is_match(value),
+        }
+    }
+}
+
+impl From<&'static str> for IgnorePattern {
+    fn from(substring: &'static str) -> IgnorePattern {
+        IgnorePattern::Substring(Cow::Borrowed(substring))
+    }
+}
+
+impl From<String> for IgnorePattern {
+    fn from(substring: String) -> IgnorePattern {
+        IgnorePattern::Substring(Cow::Owned(substring))
+    }
+}
+
+#[cfg(feature = "with_client_implementation")]
+impl From<regex::Regex> for IgnorePattern {
+    fn from(regex: regex::Regex) -> IgnorePattern {
+        IgnorePattern::Regex(regex)
+    }
+}
+
+/// The mode in which release health sessions are tracked.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SessionMode {
+    /// One session spans the whole lifetime of the application.
+    ///
+    /// Every change of the session is sent as an individual update.  This is
+    /// the mode for command line tools and desktop applications.
+    Application,
+    /// Every request a server handles is a session of its own.
+    ///
+    /// The sessions are counted per minute and sent periodically in
+    /// aggregated form.
+    Request,
+}
+
+impl Default for SessionMode {
+    fn default() -> Self {
+        SessionMode::Application
+    }
+}
+
+/// The compression the HTTP transports apply to request bodies.
+///
+/// The level ranges from 0 (fastest) to 9 (smallest).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Compression {
+    /// Request bodies are sent uncompressed.
+    None,
+    /// Request bodies are compressed with gzip at the given level.
+    Gzip(u32),
+    /// Request bodies are compressed with deflate (zlib) at the given level.
+    Deflate(u32),
+}
+
+impl Default for Compression {
+    fn default() -> Self {
+        Compression::Gzip(6)
+    }
+}
+
+/// Certificate or key data used for TLS connections.
+#[derive(Clone, PartialEq, Eq)]
+pub enum TlsSource {
+    /// The data is read from a file when the transport is created.
+    Path(PathBuf),
+    /// The data itself.
+    Bytes(Vec<u8>),
+}
+
+impl TlsSource {
+    /// Returns the data, reading it from the file if needed.
+    pub fn load(&self) -> io::Result<Vec<u8>> {
+        match *self {
+            TlsSource::Path(ref path) => fs::read(path),
+            TlsSource::Bytes(ref bytes) => Ok(bytes.clone()),
+        }
+    }
+}
+
+impl fmt::Debug for TlsSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        // the data might be a private key, so it is never printed
+        match *self {
+            TlsSource::Path(ref path) => f.debug_tuple("Path").field(path).finish(),
+            TlsSource::Bytes(ref bytes) => write!(f, "Bytes({} bytes)", bytes.len()),
+        }
+    }
+}
+
+/// The certificate the HTTP transports authenticate themselves with.
+#[derive(Clone, PartialEq, Eq)]
+pub enum ClientIdentity {
+    /// A PEM encoded certificate chain and private key.
+    ///
+    /// This is supported by `rustls` and `curl`.
+    Pem {
+        /// The certificate chain.
+        cert: TlsSource,
+        /// The private key.
+        key: TlsSource,
+    },
+    /// A DER encoded PKCS #12 archive and its password.
+    ///
+    /// This is supported by `native-tls` and `curl`.
+    Pkcs12 {
+        /// The archive.
+        archive: TlsSource,
+        /// The password of the archive.
+        password: String,
+    },
+}
+
+impl fmt::Debug for ClientIdentity {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ClientIdentity::Pem { ref cert, ref key } => f
+                .debug_struct("Pem")
+                .field("cert", cert)
+                .field("key", key)
+                .finish(),
+            ClientIdentity::Pkcs12 { ref archive, .. } => f
+                .debug_struct("Pkcs12")
+                .field("archive", archive)
+                .field("password", &"[Filtered]")
+                .finish(),
+        }
+    }
+}
+
+/// What the HTTP transports do with envelopes that do not fit into their queue.
+///
+/// Envelopes that are dropped are written to the spool instead if one is
+/// configured.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QueueOverflow {
+    /// The new envelope is dropped.
+    DropNewest,
+    /// The oldest queued envelope is dropped to make room for the new one.
+    DropOldest,
+    /// The caller blocks until there is room in the queue.  If the queue is
+    /// still full after the timeout the new envelope is dropped.
+    ///
+    /// The tokio transport treats this as `DropNewest`, as blocking would
+    /// stall the runtime its send loop runs on.
+    Block(Duration),
+}
+
+impl Default for QueueOverflow {
+    fn default() -> Self {
+        QueueOverflow::DropNewest
+    }
+}
+
+/// The longest delay between two attempts, longer ones are shortened.
+const MAX_RETRY_DELAY: Duration = Duration::from_secs(60 * 60);
+
+/// How the HTTP transports retry sending after transient failures.
+///
+/// Connection errors and responses with a retryable status code are retried
+/// with exponential backoff.  Envelopes that could not be sent after the last
+/// attempt are spooled or discarded.  No retries happen once the transport
+/// is shutting down.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RetryPolicy {
+    /// The maximum number of attempts per envelope including the first one.
+    /// (defaults to 3, 1 disables retries)
+    pub max_attempts: u32,
+    /// The delay before the first retry, which doubles with every further
+    /// retry. (defaults to 500ms)
+    pub base_delay: Duration,
+    /// The fraction of every delay that is randomized. (0.0 - 1.0, defaults
+    /// to 0.5)
+    pub jitter: f32,
+    /// The response status codes that are retried. (defaults to 500, 502,
+    /// 503 and 504)
+    pub retryable_status_codes: Vec<u16>,
+}
+
+impl RetryPolicy {
+    /// Returns whether a response with the given status code is retried.
+    pub fn is_retryable(&self, status_code: u16) -> bool {
+        self.retryable_status_codes.contains(&status_code)
+    }
+
+    /// Returns the delay before the given retry without jitter.
+    ///
+    /// The first retry is number 1.  The delay is capped at one hour.
+    pub fn delay(&self, retry: u32) -> Duration {
+        // `u32::MAX` is not available on all supported compilers
+        #[allow(clippy::legacy_numeric_constants)]
+        let factor = 1u32
+            .checked_shl(retry.saturating_sub(1))
+            .unwrap_or(std::u32::MAX);
+        self.base_delay
+            .checked_mul(factor)
+            .map_or(MAX_RETRY_DELAY, |delay| delay.min(MAX_RETRY_DELAY))
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(500),
+            jitter: 0.5,
+            retryable_status_codes: vec![500, 502, 503, 504],
+        }
+    }
+}
+
 /// Configuration settings for the client.
 ///
 /// These options are explained in more detail in the general
@@ -34,6 +282,20 @@ pub struct ClientOptions {
     pub environment: Option<Cow<'static, str>>,
     /// The sample rate for event submission. (0.0 - 1.0, defaults to 1.0)
     pub sample_rate: f32,
+    /// Callback deciding the sample rate of each event.
+    ///
+    /// When set this is used instead of the `sample_rate`.  It is invoked
+    /// with the prepared event right before `before_send`, so the level,
+    /// logger, tags and everything else the scope and integrations added can
+    /// be taken into account.  The chosen rate is recorded in the
+    /// `sample_rate` header of the envelope carrying the event.
+    pub sampler: Option<EventSampler>,
+    /// The sample rate for transactions. (0.0 - 1.0, defaults to 0.0)
+    pub traces_sample_rate: f32,
+    /// Callback deciding the sample rate of each transaction.
+    ///
+    /// When set this is used instead of the `traces_sample_rate`.
+    pub traces_sampler: Option<TracesSampler>,
     /// Maximum number of breadcrumbs. (defaults to 100)
     pub max_breadcrumbs: usize,
     /// Attaches stacktraces to messages.
@@ -46,6 +308,13 @@ pub struct ClientOptions {
     pub in_app_include: Vec<&'static str>,
     /// Module prefixes that are never "in_app".
     pub in_app_exclude: Vec<&'static str>,
+    /// Patterns of errors that are never sent.
+    ///
+    /// An event is dropped if one of the patterns matches the type or value
+    /// of one of its exceptions or its message.
+    pub ignore_errors: Vec<IgnorePattern>,
+    /// Patterns of transaction names that are never sent.
+    pub ignore_transactions: Vec<IgnorePattern>,
     // Integration options
     /// A list of integrations to enable.
     pub integrations: Vec<Arc<dyn Integration>>,
@@ -72,8 +341,55 @@ pub struct ClientOptions {
     /// This will default to the `HTTPS_PROXY` environment variable
     /// or `http_proxy` if that one exists.
     pub https_proxy: Option<Cow<'static, str>>,
+    /// Additional PEM encoded root certificates the HTTP transports trust.
+    ///
+    /// With `curl` these replace the default CA bundle.  TLS options are not
+    /// applied to HTTP clients passed to a transport's `with_client`.
+    pub ca_certs: Vec<TlsSource>,
+    /// The certificate the HTTP transports present to the server.
+    pub client_identity: Option<ClientIdentity>,
+    /// Disables the validation of server certificates.
+    ///
+    /// This is dangerous and only meant for development setups.
+    pub accept_invalid_certs: bool,
+    /// An optional directory in which undeliverable envelopes are kept.
+    ///
+    /// When set, the HTTP transports write envelopes that cannot be sent
+    /// because the network is down or the queue is full into this directory
+    /// and send them once Sentry can be reached again, at the latest on the
+    /// next start.  The directory should not be shared between DSNs.
+    pub spool_dir: Option<PathBuf>,
+    /// The maximum total size of the spool directory in bytes.
+    ///
+    /// The oldest envelopes are discarded first. (defaults to 10 MiB)
+    pub spool_max_size: u64,
+    /// The maximum age of spooled envelopes. (defaults to one day)
+    pub spool_max_age: Duration,
+    /// The compression of request bodies. (defaults to gzip at level 6)
+    pub compression: Compression,
+    /// The policy for retrying transient failures in the HTTP transports.
+    pub retry_policy: RetryPolicy,
+    /// The number of envelopes the HTTP transports queue. (defaults to 30)
+    pub queue_capacity: usize,
+    /// What happens to envelopes when the queue is full. (defaults to
+    /// `DropNewest`)
+    pub queue_overflow: QueueOverflow,
+    /// Enables release health session tracking.
+    ///
+    /// When enabled `sentry::init` starts a session which lasts until the
+    /// returned guard is dropped.  In the `Request` session mode the web
+    /// framework integrations start a session per request instead.  Sessions
+    /// are only recorded if a `release` is configured.
+    pub auto_session_tracking: bool,
+    /// The mode in which sessions are tracked. (defaults to `Application`)
+    pub session_mode: SessionMode,
     /// The timeout on client drop for draining events on shutdown.
     pub shutdown_timeout: Duration,
+    /// Enables sending client reports. (defaults to true)
+    ///
+    /// Client reports tell Sentry how many events and other items the SDK
+    /// discarded, for instance because of sampling or rate limits.
+    pub send_client_reports: bool,
     // Other options not documented in Unified API
     /// Border frames which indicate a border from a backtrace to
     /// useless internals. Some are automatically included.
@@ -101,6 +417,12 @@ impl fmt::Debug for ClientOptions {
         struct BeforeBreadcrumb;
         let before_breadcrumb = self.before_breadcrumb.as_ref().map(|_| BeforeBreadcrumb);
         #[derive(Debug)]
+        struct EventSampler;
+        let sampler = self.sampler.as_ref().map(|_| EventSampler);
+        #[derive(Debug)]
+        struct TracesSampler;
+        let traces_sampler = self.traces_sampler.as_ref().map(|_| TracesSampler);
+        #[derive(Debug)]
         struct TransportFactory;
 
         let integrations: Vec<_> = self.integrations.iter().map(|i| i.name()).collect();
@@ -111,12 +433,17 @@ impl fmt::Debug for ClientOptions {
             .field("release", &self.release)
             .field("environment", &self.environment)
             .field("sample_rate", &self.sample_rate)
+            .field("sampler", &sampler)
+            .field("traces_sample_rate", &self.traces_sample_rate)
+            .field("traces_sampler", &traces_sampler)
             .field("max_breadcrumbs", &self.max_breadcrumbs)
             .field("attach_stacktrace", &self.attach_stacktrace)
             .field("send_default_pii", &self.send_default_pii)
             .field("server_name", &self.server_name)
             .field("in_app_include", &self.in_app_include)
             .field("in_app_exclude", &self.in_app_exclude)
+            .field("ignore_errors", &self.ignore_errors)
+            .field("ignore_transactions", &self.ignore_transactions)
             .field("integrations", &integrations)
             .field("default_integrations", &self.default_integrations)
             .field("before_send", &before_send)
@@ -124,7 +451,20 @@ impl fmt::Debug for ClientOptions {
             .field("transport", &TransportFactory)
             .field("http_proxy", &self.http_proxy)
             .field("https_proxy", &self.https_proxy)
+            .field("ca_certs", &self.ca_certs)
+            .field("client_identity", &self.client_identity)
+            .field("accept_invalid_certs", &self.accept_invalid_certs)
+            .field("spool_dir", &self.spool_dir)
+            .field("spool_max_size", &self.spool_max_size)
+            .field("spool_max_age", &self.spool_max_age)
+            .field("compression", &self.compression)
+            .field("retry_policy", &self.retry_policy)
+            .field("queue_capacity", &self.queue_capacity)
+            .field("queue_overflow", &self.queue_overflow)
+            .field("auto_session_tracking", &self.auto_session_tracking)
+            .field("session_mode", &self.session_mode)
             .field("shutdown_timeout", &self.shutdown_timeout)
+            .field("send_client_reports", &self.send_client_reports)
             .field("extra_border_frames", &self.extra_border_frames)
             .field("trim_backtraces", &self.trim_backtraces)
             .field("user_agent", &self.user_agent)
@@ -140,12 +480,17 @@ impl Default for ClientOptions {
             release: None,
             environment: None,
             sample_rate: 1.0,
+            sampler: None,
+            traces_sample_rate: 0.0,
+            traces_sampler: None,
             max_breadcrumbs: 100,
             attach_stacktrace: false,
             send_default_pii: false,
             server_name: None,
             in_app_include: vec![],
             in_app_exclude: vec![],
+            ignore_errors: vec![],
+            ignore_transactions: vec![],
             integrations: vec![],
             default_integrations: true,
             before_send: None,
@@ -153,7 +498,20 @@ impl Default for ClientOptions {
             transport: None,
             http_proxy: None,
             https_proxy: None,
+            ca_certs: vec![],
+            client_identity: None,
+            accept_invalid_certs: false,
+            spool_dir: None,
+            spool_max_size: 10 * 1024 * 1024,
+            spool_max_age: Duration::from_secs(24 * 60 * 60),
+            compression: Compression::Gzip(6),
+            retry_policy: RetryPolicy::default(),
+            queue_capacity: 30,
+            queue_overflow: QueueOverflow::DropNewest,
+            auto_session_tracking: false,
+            session_mode: SessionMode::Application,
             shutdown_timeout: Duration::from_secs(2),
+            send_client_reports: true,
             extra_border_frames: vec![],
             trim_backtraces: true,
             user_agent: Cow::Borrowed(&USER_AGENT),
@@ -50,6 +50,17 @@ pub trait FutureExt: Sized {
             hub: hub.into(),
         }
     }
+
+    /// Binds a new hub based on the current hub to the execution of this future.
+    ///
+    /// The hub is created with `Hub::new_from_top(Hub::current())` when this
+    /// is called, so the future starts out with the current scope.  Changes
+    /// the future makes to the scope stay with its own hub and never leak
+    /// into the hub of the caller or of other futures.
+    #[cfg(feature = "with_client_implementation")]
+    fn fork_hub(self) -> SentryFuture<Self> {
+        self.bind_hub(Hub::new_from_top(Hub::current()))
+    }
 }
 
 impl<F> FutureExt for F where F: Future {}
@@ -93,4 +104,36 @@ mod tests {
         assert_eq!(events[1].transaction, Some("transaction1".into()));
         assert_eq!(events[2].transaction, Some("transaction2".into()));
     }
+
+    #[test]
+    fn test_fork_hub() {
+        let mut events = with_captured_events(|| {
+            let mut runtime = Runtime::new().unwrap();
+            configure_scope(|scope| scope.set_tag("outer", "yes"));
+
+            runtime.block_on(async {
+                let task1 = async {
+                    configure_scope(|scope| scope.set_transaction(Some("transaction1")));
+                    capture_message("oh hai from 1", Level::Info);
+                }
+                .fork_hub();
+                let task2 = async {
+                    capture_message("oh hai from 2", Level::Info);
+                }
+                .fork_hub();
+                tokio::join!(task1, task2);
+            });
+
+            capture_message("oh hai from outside", Level::Info);
+        });
+
+        events.sort_by(|a, b| a.message.cmp(&b.message));
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0].transaction, Some("transaction1".into()));
+        assert_eq!(events[1].transaction, None);
+        assert_eq!(events[2].transaction, None);
+        for event in &events {
+            assert_eq!(event.tags["outer"], "yes");
+        }
+    }
 }
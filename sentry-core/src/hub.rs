@@ -13,9 +13,12 @@ use crate::breadcrumbs::IntoBreadcrumbs;
 use crate::error::event_from_error;
 use crate::integrations::Integration;
 use crate::internals::Uuid;
+use crate::performance::{Transaction, TransactionContext};
 use crate::protocol::{Breadcrumb, Event, Level};
 use crate::scope::{Scope, ScopeGuard};
 #[cfg(feature = "with_client_implementation")]
+use crate::session::Session;
+#[cfg(feature = "with_client_implementation")]
 use crate::{client::Client, scope::Stack};
 
 #[cfg(feature = "with_client_implementation")]
@@ -225,6 +228,31 @@ impl Hub {
         }
     }
 
+    /// Binds a closure to a new hub based on the current hub.
+    ///
+    /// The hub is created right away with `Hub::new_from_top(Hub::current())`
+    /// and bound with `Hub::run` whenever the returned closure is invoked.
+    /// This carries the scope over to closures that run on other threads,
+    /// which would otherwise use a hub based on `Hub::main()`.
+    ///
+    /// # Example
+    /// ```
+    /// # use sentry_core as sentry;
+    /// use std::thread;
+    ///
+    /// sentry::configure_scope(|scope| scope.set_tag("worker", "worker1"));
+    /// thread::spawn(sentry::Hub::bind_to_closure(|| {
+    ///     // events captured here are tagged with `worker`
+    /// }))
+    /// .join()
+    /// .unwrap();
+    /// ```
+    #[cfg(feature = "with_client_implementation")]
+    pub fn bind_to_closure<F: FnOnce() -> R, R>(f: F) -> impl FnOnce() -> R {
+        let hub = Arc::new(Hub::new_from_top(Hub::current()));
+        move || Hub::run(hub, f)
+    }
+
     /// Looks up an integration on the hub.
     ///
     /// Calls the given function with the requested integration instance when it
@@ -305,6 +333,68 @@ impl Hub {
         }}
     }
 
+    /// Starts a new performance monitoring transaction.
+    ///
+    /// This works the same as the global `start_transaction` function.
+    pub fn start_transaction(&self, ctx: TransactionContext) -> Transaction {
+        #[cfg(feature = "with_client_implementation")]
+        {
+            Transaction::new(self.client(), ctx)
+        }
+        #[cfg(not(feature = "with_client_implementation"))]
+        {
+            Transaction::new_noop(ctx)
+        }
+    }
+
+    /// Starts a new release health session.
+    ///
+    /// The session is bound to the current scope and replaces any session
+    /// that was started on it before.  Sessions are only started if the
+    /// client has a `release` configured.
+    pub fn start_session(&self) {
+        with_client_impl! {{
+            self.inner.with_mut(|stack| {
+                let top = stack.top_mut();
+                if let Some(session) = Session::from_stack(top) {
+                    // the new session must not be shared with the scopes the
+                    // current one was cloned from.
+                    let scope = Arc::make_mut(&mut top.scope);
+                    scope.session = Arc::new(Mutex::new(Some(session)));
+                }
+            })
+        }}
+    }
+
+    /// Ends the session of the current scope.
+    ///
+    /// The session is closed and its final state is sent to Sentry.
+    pub fn end_session(&self) {
+        with_client_impl! {{
+            let session = self.with_current_scope(|scope| scope.session.lock().unwrap().take());
+            drop(session);
+        }}
+    }
+
+    /// Waits until the events queued on the bound client have been sent.
+    ///
+    /// See [`Client::flush`](struct.Client.html#method.flush).  Returns
+    /// `true` if there is no client or its queue was drained in time.
+    pub fn flush(&self, timeout: Option<Duration>) -> bool {
+        #[cfg(feature = "with_client_implementation")]
+        {
+            match self.client() {
+                Some(client) => client.flush(timeout),
+                None => true,
+            }
+        }
+        #[cfg(not(feature = "with_client_implementation"))]
+        {
+            let _timeout = timeout;
+            true
+        }
+    }
+
     /// Returns the currently bound client.
     #[cfg(feature = "with_client_implementation")]
     pub fn client(&self) -> Option<Arc<Client>> {
@@ -325,6 +415,22 @@ impl Hub {
         }}
     }
 
+    /// Returns a snapshot of the current scope.
+    ///
+    /// The snapshot shares its data with the scope, so taking it is cheap.
+    /// Later changes to the scope of the hub are not reflected in it.  In
+    /// minimal mode this returns an empty scope.
+    pub fn scope_snapshot(&self) -> Arc<Scope> {
+        #[cfg(feature = "with_client_implementation")]
+        {
+            self.inner.with(|stack| stack.top().scope.clone())
+        }
+        #[cfg(not(feature = "with_client_implementation"))]
+        {
+            Arc::new(Scope)
+        }
+    }
+
     /// Pushes a new scope.
     ///
     /// This returns a guard that when dropped will pop the scope again.
@@ -0,0 +1,168 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::protocol::{Event, Stacktrace};
+use crate::{ClientOptions, Integration};
+
+/// What makes two events the same issue.
+#[derive(Debug, PartialEq)]
+struct Signature {
+    exceptions: Vec<(String, Option<String>, Option<Stacktrace>)>,
+    message: Option<String>,
+    fingerprint: Vec<String>,
+}
+
+impl Signature {
+    fn from_event(event: &Event<'static>) -> Signature {
+        Signature {
+            exceptions: event
+                .exception
+                .iter()
+                .map(|exc| (exc.ty.clone(), exc.value.clone(), exc.stacktrace.clone()))
+                .collect(),
+            message: event
+                .message
+                .clone()
+                .or_else(|| event.logentry.as_ref().map(|entry| entry.message.clone())),
+            fingerprint: event.fingerprint.iter().map(|s| s.to_string()).collect(),
+        }
+    }
+
+    fn is_duplicate_of(&self, previous: &Signature) -> bool {
+        if !self.exceptions.is_empty() {
+            self.exceptions == previous.exceptions
+        } else {
+            self.message.is_some()
+                && self.message == previous.message
+                && self.fingerprint == previous.fingerprint
+        }
+    }
+}
+
+/// Drops events that repeat the previous event.
+///
+/// An error that bubbles through several layers of an application is often
+/// captured more than once.  This integration drops an event if it has the
+/// same exceptions, including their stacktraces, or the same message and
+/// fingerprint as the previous event, provided the previous event was
+/// captured less than `window` ago.
+///
+/// The integration is enabled by default in `sentry`.
+pub struct DedupeIntegration {
+    window: Duration,
+    previous: Mutex<Option<(Instant, Signature)>>,
+}
+
+impl Default for DedupeIntegration {
+    fn default() -> Self {
+        Self::with_window(Duration::from_secs(60))
+    }
+}
+
+impl DedupeIntegration {
+    /// Creates a new Dedupe Integration with a window of one minute.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a new Dedupe Integration with the given window.
+    pub fn with_window(window: Duration) -> Self {
+        Self {
+            window,
+            previous: Mutex::new(None),
+        }
+    }
+}
+
+impl Integration for DedupeIntegration {
+    fn name(&self) -> &'static str {
+        "dedupe"
+    }
+
+    fn process_event(
+        &self,
+        event: Event<'static>,
+        _options: &ClientOptions,
+    ) -> Option<Event<'static>> {
+        let signature = Signature::from_event(&event);
+        let now = Instant::now();
+        let mut previous = self.previous.lock().unwrap();
+        if let Some((captured, ref previous_signature)) = *previous {
+            if now.duration_since(captured) < self.window
+                && signature.is_duplicate_of(previous_signature)
+            {
+                sentry_debug!("dropping duplicate event");
+                return None;
+            }
+        }
+        *previous = Some((now, signature));
+        Some(event)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{Exception, Frame};
+
+    fn error(value: &str) -> Event<'static> {
+        Event {
+            exception: vec![Exception {
+                ty: "Error".into(),
+                value: Some(value.into()),
+                stacktrace: Some(Stacktrace {
+                    frames: vec![Frame {
+                        function: Some("main".into()),
+                        ..Default::default()
+                    }],
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }]
+            .into(),
+            ..Default::default()
+        }
+    }
+
+    fn message(message: &str, fingerprint: &'static str) -> Event<'static> {
+        Event {
+            message: Some(message.into()),
+            fingerprint: vec![fingerprint.into()].into(),
+            ..Default::default()
+        }
+    }
+
+    fn is_kept(integration: &DedupeIntegration, event: Event<'static>) -> bool {
+        integration
+            .process_event(event, &ClientOptions::default())
+            .is_some()
+    }
+
+    #[test]
+    fn test_dedupe_exceptions() {
+        let integration = DedupeIntegration::new();
+        assert!(is_kept(&integration, error("oops")));
+        assert!(!is_kept(&integration, error("oops")));
+        assert!(is_kept(&integration, error("other")));
+        assert!(is_kept(&integration, error("oops")));
+    }
+
+    #[test]
+    fn test_dedupe_messages() {
+        let integration = DedupeIntegration::new();
+        assert!(is_kept(&integration, message("hello", "a")));
+        assert!(!is_kept(&integration, message("hello", "a")));
+        assert!(is_kept(&integration, message("hello", "b")));
+        assert!(is_kept(&integration, Event::default()));
+        assert!(is_kept(&integration, Event::default()));
+    }
+
+    #[test]
+    fn test_dedupe_window() {
+        let integration = DedupeIntegration::with_window(Duration::from_millis(10));
+        assert!(is_kept(&integration, error("oops")));
+        std::thread::sleep(Duration::from_millis(20));
+        assert!(is_kept(&integration, error("oops")));
+    }
+}
@@ -7,6 +7,12 @@ use std::any::{type_name, Any};
 use crate::protocol::Event;
 use crate::ClientOptions;
 
+#[cfg(feature = "with_client_implementation")]
+mod dedupe;
+
+#[cfg(feature = "with_client_implementation")]
+pub use self::dedupe::DedupeIntegration;
+
 /// Integration abstraction.
 ///
 /// An Integration in sentry has two primary purposes.
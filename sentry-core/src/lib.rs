@@ -97,12 +97,16 @@ mod api;
 mod breadcrumbs;
 mod futures;
 mod hub;
+mod performance;
 mod scope;
+#[cfg(feature = "with_client_implementation")]
+mod session;
 
 pub mod integrations;
 
 #[cfg(feature = "with_client_implementation")]
 mod client;
+mod client_reports;
 mod clientoptions;
 mod constants;
 mod error;
@@ -121,6 +125,7 @@ pub mod internals {
     pub use crate::breadcrumbs::IntoBreadcrumbs;
     pub use crate::scope::ScopeGuard;
 
+    pub use crate::client_reports::ClientReportRecorder;
     pub use crate::intodsn::IntoDsn;
     pub use crate::transport::{Transport, TransportFactory};
 
@@ -132,11 +137,15 @@ pub mod internals {
 
 // public api or exports from this crate
 pub use crate::api::*;
-pub use crate::clientoptions::ClientOptions;
+pub use crate::clientoptions::{
+    ClientIdentity, ClientOptions, Compression, IgnorePattern, QueueOverflow, RetryPolicy,
+    SessionMode, TlsSource,
+};
 pub use crate::error::{capture_error, event_from_error, parse_type_from_debug};
 pub use crate::futures::{FutureExt, SentryFuture as Future};
 pub use crate::hub::Hub;
 pub use crate::integrations::Integration;
+pub use crate::performance::{Span, Transaction, TransactionContext, TransactionOrSpan};
 pub use crate::scope::Scope;
 
 #[cfg(feature = "with_client_implementation")]
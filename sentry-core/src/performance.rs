@@ -0,0 +1,409 @@
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "with_client_implementation")]
+use crate::client::Client;
+use crate::protocol::{self, SpanId, SpanStatus, TraceContext, TraceId, Value};
+#[cfg(feature = "with_client_implementation")]
+use crate::protocol::{DataCategory, DiscardReason};
+
+/// The context of a transaction that is about to be started.
+///
+/// It holds the name and operation of the transaction together with the trace
+/// it belongs to.  When continuing a trace from an upstream service the
+/// sampling decision of that service can be carried over as well.
+#[derive(Debug, Clone)]
+pub struct TransactionContext {
+    name: String,
+    op: String,
+    trace_id: TraceId,
+    parent_span_id: Option<SpanId>,
+    sampled: Option<bool>,
+}
+
+impl TransactionContext {
+    /// Creates a new transaction context that starts a new trace.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// # use sentry_core as sentry;
+    /// let ctx = sentry::TransactionContext::new("GET /users", "http.server");
+    /// let transaction = sentry::start_transaction(ctx);
+    /// transaction.finish();
+    /// ```
+    pub fn new(name: &str, op: &str) -> TransactionContext {
+        TransactionContext {
+            name: name.to_string(),
+            op: op.to_string(),
+            trace_id: Default::default(),
+            parent_span_id: None,
+            sampled: None,
+        }
+    }
+
+    /// Creates a new transaction context that continues the trace of the
+    /// given transaction or span.
+    ///
+    /// The new transaction inherits the trace and the sampling decision of
+    /// the span.  If no span is given this is the same as `new`.
+    pub fn continue_from_span(
+        name: &str,
+        op: &str,
+        span: Option<&TransactionOrSpan>,
+    ) -> TransactionContext {
+        let mut ctx = TransactionContext::new(name, op);
+        if let Some(span) = span {
+            let trace_context = span.get_trace_context();
+            ctx.trace_id = trace_context.trace_id;
+            ctx.parent_span_id = Some(trace_context.span_id);
+            ctx.sampled = Some(span.is_sampled());
+        }
+        ctx
+    }
+
+    /// Returns the name of the transaction.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the operation of the transaction.
+    pub fn operation(&self) -> &str {
+        &self.op
+    }
+
+    /// Returns the trace id of the transaction.
+    pub fn trace_id(&self) -> TraceId {
+        self.trace_id
+    }
+
+    /// Returns the sampling decision if one was already made.
+    pub fn sampled(&self) -> Option<bool> {
+        self.sampled
+    }
+
+    /// Overrides the sampling decision.
+    ///
+    /// A transaction with an explicit sampling decision is not passed to the
+    /// `traces_sampler` and ignores the `traces_sample_rate`.
+    pub fn set_sampled(&mut self, sampled: Option<bool>) {
+        self.sampled = sampled;
+    }
+}
+
+#[derive(Debug)]
+struct TransactionInner {
+    #[cfg(feature = "with_client_implementation")]
+    client: Option<Arc<Client>>,
+    sampled: bool,
+    context: TraceContext,
+    transaction: Option<protocol::Transaction<'static>>,
+}
+
+/// A running performance monitoring transaction.
+///
+/// Transactions are started with [`start_transaction`](fn.start_transaction.html)
+/// and are only sent to Sentry once they are explicitly finished.  Cloning a
+/// transaction is cheap, all clones refer to the same transaction.
+#[derive(Debug, Clone)]
+pub struct Transaction {
+    inner: Arc<Mutex<TransactionInner>>,
+}
+
+impl Transaction {
+    #[cfg(feature = "with_client_implementation")]
+    pub(crate) fn new(client: Option<Arc<Client>>, ctx: TransactionContext) -> Transaction {
+        let sampled = match client {
+            Some(ref client) if client.is_transaction_ignored(&ctx) => {
+                client.record_discard(DiscardReason::Filtered, DataCategory::Transaction);
+                false
+            }
+            Some(ref client) if !client.is_transaction_sampled(&ctx) => {
+                client.record_discard(DiscardReason::SampleRate, DataCategory::Transaction);
+                false
+            }
+            Some(_) => true,
+            None => false,
+        };
+        let transaction = Transaction::with_context(ctx, sampled);
+        if sampled {
+            transaction.inner.lock().unwrap().client = client;
+        }
+        transaction
+    }
+
+    #[cfg(not(feature = "with_client_implementation"))]
+    pub(crate) fn new_noop(ctx: TransactionContext) -> Transaction {
+        Transaction::with_context(ctx, false)
+    }
+
+    fn with_context(ctx: TransactionContext, sampled: bool) -> Transaction {
+        let context = TraceContext {
+            trace_id: ctx.trace_id,
+            parent_span_id: ctx.parent_span_id,
+            op: Some(ctx.op),
+            ..Default::default()
+        };
+        // unsampled transactions never leave the process so there is no
+        // point in recording any data on them.
+        let transaction = if sampled {
+            Some(protocol::Transaction {
+                name: Some(ctx.name),
+                ..Default::default()
+            })
+        } else {
+            None
+        };
+        Transaction {
+            inner: Arc::new(Mutex::new(TransactionInner {
+                #[cfg(feature = "with_client_implementation")]
+                client: None,
+                sampled,
+                context,
+                transaction,
+            })),
+        }
+    }
+
+    /// Returns the trace context of the transaction.
+    pub fn get_trace_context(&self) -> TraceContext {
+        self.inner.lock().unwrap().context.clone()
+    }
+
+    /// Returns whether the transaction is sent to Sentry when finished.
+    pub fn is_sampled(&self) -> bool {
+        self.inner.lock().unwrap().sampled
+    }
+
+    /// Sets the status of the transaction.
+    pub fn set_status(&self, status: SpanStatus) {
+        self.inner.lock().unwrap().context.status = Some(status);
+    }
+
+    /// Sets a tag to a specific value.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn set_tag<V: ToString>(&self, key: &str, value: V) {
+        if let Some(ref mut transaction) = self.inner.lock().unwrap().transaction {
+            transaction.tags.insert(key.to_string(), value.to_string());
+        }
+    }
+
+    /// Sets some arbitrary data on the transaction.
+    pub fn set_data(&self, key: &str, value: Value) {
+        if let Some(ref mut transaction) = self.inner.lock().unwrap().transaction {
+            transaction.extra.insert(key.to_string(), value);
+        }
+    }
+
+    /// Starts a new child span of the transaction.
+    pub fn start_child(&self, op: &str, description: &str) -> Span {
+        let inner = self.inner.lock().unwrap();
+        let span = protocol::Span {
+            trace_id: inner.context.trace_id,
+            parent_span_id: Some(inner.context.span_id),
+            op: Some(op.to_string()),
+            description: Some(description.to_string()),
+            ..Default::default()
+        };
+        Span {
+            transaction: self.inner.clone(),
+            sampled: inner.sampled,
+            span: Arc::new(Mutex::new(span)),
+        }
+    }
+
+    /// Finishes the transaction and sends it to Sentry if it was sampled.
+    ///
+    /// Only spans that were finished before the transaction are sent along.
+    /// Finishing a transaction more than once has no effect.
+    pub fn finish(self) {
+        with_client_impl! {{
+            let mut inner = self.inner.lock().unwrap();
+            if let Some(mut transaction) = inner.transaction.take() {
+                if let Some(client) = inner.client.take() {
+                    transaction.finish();
+                    transaction
+                        .contexts
+                        .insert("trace".into(), inner.context.clone().into());
+                    client.capture_transaction(transaction);
+                }
+            }
+        }}
+    }
+}
+
+/// A running span within a transaction.
+///
+/// Spans are created with `start_child` on a transaction or another span.
+/// A span has to be finished before its transaction for it to be sent along
+/// with it.  Cloning a span is cheap, all clones refer to the same span.
+#[derive(Debug, Clone)]
+pub struct Span {
+    transaction: Arc<Mutex<TransactionInner>>,
+    sampled: bool,
+    span: Arc<Mutex<protocol::Span>>,
+}
+
+impl Span {
+    /// Returns the trace context of the span.
+    pub fn get_trace_context(&self) -> TraceContext {
+        let span = self.span.lock().unwrap();
+        TraceContext {
+            span_id: span.span_id,
+            trace_id: span.trace_id,
+            parent_span_id: span.parent_span_id,
+            op: span.op.clone(),
+            description: span.description.clone(),
+            status: span.status,
+        }
+    }
+
+    /// Returns whether the span is sent to Sentry with its transaction.
+    pub fn is_sampled(&self) -> bool {
+        self.sampled
+    }
+
+    /// Sets the status of the span.
+    pub fn set_status(&self, status: SpanStatus) {
+        self.span.lock().unwrap().status = Some(status);
+    }
+
+    /// Sets a tag to a specific value.
+    #[allow(clippy::needless_pass_by_value)]
+    pub fn set_tag<V: ToString>(&self, key: &str, value: V) {
+        let mut span = self.span.lock().unwrap();
+        span.tags.insert(key.to_string(), value.to_string());
+    }
+
+    /// Sets some arbitrary data on the span.
+    pub fn set_data(&self, key: &str, value: Value) {
+        self.span
+            .lock()
+            .unwrap()
+            .data
+            .insert(key.to_string(), value);
+    }
+
+    /// Starts a new child span of this span.
+    pub fn start_child(&self, op: &str, description: &str) -> Span {
+        let parent = self.span.lock().unwrap();
+        let span = protocol::Span {
+            trace_id: parent.trace_id,
+            parent_span_id: Some(parent.span_id),
+            op: Some(op.to_string()),
+            description: Some(description.to_string()),
+            ..Default::default()
+        };
+        Span {
+            transaction: self.transaction.clone(),
+            sampled: self.sampled,
+            span: Arc::new(Mutex::new(span)),
+        }
+    }
+
+    /// Finishes the span and records it on its transaction.
+    ///
+    /// Finishing a span more than once has no effect.
+    pub fn finish(self) {
+        let mut span = self.span.lock().unwrap();
+        if span.timestamp.is_some() {
+            return;
+        }
+        span.finish();
+        let mut inner = self.transaction.lock().unwrap();
+        if let Some(ref mut transaction) = inner.transaction {
+            transaction.spans.push(span.clone());
+        }
+    }
+}
+
+/// Either a transaction or a span.
+///
+/// This is what is stored as the active span on the
+/// [`Scope`](struct.Scope.html).
+#[derive(Debug, Clone)]
+pub enum TransactionOrSpan {
+    /// A transaction.
+    Transaction(Transaction),
+    /// A span.
+    Span(Span),
+}
+
+impl From<Transaction> for TransactionOrSpan {
+    fn from(transaction: Transaction) -> Self {
+        TransactionOrSpan::Transaction(transaction)
+    }
+}
+
+impl From<Span> for TransactionOrSpan {
+    fn from(span: Span) -> Self {
+        TransactionOrSpan::Span(span)
+    }
+}
+
+impl TransactionOrSpan {
+    /// Returns the trace context of the transaction or span.
+    pub fn get_trace_context(&self) -> TraceContext {
+        match self {
+            TransactionOrSpan::Transaction(transaction) => transaction.get_trace_context(),
+            TransactionOrSpan::Span(span) => span.get_trace_context(),
+        }
+    }
+
+    /// Returns whether the transaction or span is sampled.
+    pub fn is_sampled(&self) -> bool {
+        match self {
+            TransactionOrSpan::Transaction(transaction) => transaction.is_sampled(),
+            TransactionOrSpan::Span(span) => span.is_sampled(),
+        }
+    }
+
+    /// Sets the status of the transaction or span.
+    pub fn set_status(&self, status: SpanStatus) {
+        match self {
+            TransactionOrSpan::Transaction(transaction) => transaction.set_status(status),
+            TransactionOrSpan::Span(span) => span.set_status(status),
+        }
+    }
+
+    /// Sets a tag to a specific value.
+    pub fn set_tag<V: ToString>(&self, key: &str, value: V) {
+        match self {
+            TransactionOrSpan::Transaction(transaction) => transaction.set_tag(key, value),
+            TransactionOrSpan::Span(span) => span.set_tag(key, value),
+        }
+    }
+
+    /// Sets some arbitrary data.
+    pub fn set_data(&self, key: &str, value: Value) {
+        match self {
+            TransactionOrSpan::Transaction(transaction) => transaction.set_data(key, value),
+            TransactionOrSpan::Span(span) => span.set_data(key, value),
+        }
+    }
+
+    /// Starts a new child span.
+    pub fn start_child(&self, op: &str, description: &str) -> Span {
+        match self {
+            TransactionOrSpan::Transaction(transaction) => transaction.start_child(op, description),
+            TransactionOrSpan::Span(span) => span.start_child(op, description),
+        }
+    }
+
+    /// Finishes the transaction or span.
+    pub fn finish(self) {
+        match self {
+            TransactionOrSpan::Transaction(transaction) => transaction.finish(),
+            TransactionOrSpan::Span(span) => span.finish(),
+        }
+    }
+
+    /// Attaches the trace context to an event unless it already has one.
+    #[cfg(feature = "with_client_implementation")]
+    pub(crate) fn apply_to_event(&self, event: &mut protocol::Event<'static>) {
+        if !event.contexts.contains_key("trace") {
+            event
+                .contexts
+                .insert("trace".into(), self.get_trace_context().into());
+        }
+    }
+}
@@ -1,6 +1,8 @@
 use std::fmt;
+use std::iter::{self, Empty};
 
-use crate::protocol::{Context, Event, Level, User, Value};
+use crate::performance::TransactionOrSpan;
+use crate::protocol::{Attachment, Breadcrumb, Context, Event, Level, User, Value};
 
 /// The minimal scope.
 ///
@@ -94,6 +96,63 @@ impl Scope {
         minimal_unreachable!();
     }
 
+    /// Returns the level override.
+    pub fn level(&self) -> Option<Level> {
+        None
+    }
+
+    /// Returns the transaction.
+    pub fn transaction(&self) -> Option<&str> {
+        None
+    }
+
+    /// Returns the user of the scope.
+    pub fn user(&self) -> Option<&User> {
+        None
+    }
+
+    /// Returns an iterator over the tags.
+    pub fn tags(&self) -> Empty<(&str, &str)> {
+        iter::empty()
+    }
+
+    /// Returns an iterator over the extra values.
+    pub fn extra(&self) -> Empty<(&str, &Value)> {
+        iter::empty()
+    }
+
+    /// Returns an iterator over the contexts.
+    pub fn contexts(&self) -> Empty<(&str, &Context)> {
+        iter::empty()
+    }
+
+    /// Returns an iterator over the breadcrumbs, oldest first.
+    pub fn breadcrumbs(&self) -> Empty<&Breadcrumb> {
+        iter::empty()
+    }
+
+    /// Sets the currently active transaction or span.
+    pub fn set_span(&mut self, span: Option<TransactionOrSpan>) {
+        let _span = span;
+        minimal_unreachable!();
+    }
+
+    /// Returns the currently active transaction or span.
+    pub fn get_span(&self) -> Option<TransactionOrSpan> {
+        None
+    }
+
+    /// Adds an attachment to the scope.
+    pub fn add_attachment(&mut self, attachment: Attachment) {
+        let _attachment = attachment;
+        minimal_unreachable!();
+    }
+
+    /// Clears all attachments from the scope.
+    pub fn clear_attachments(&mut self) {
+        minimal_unreachable!();
+    }
+
     /// Add an event processor to the scope.
     pub fn add_event_processor(
         &mut self,
@@ -109,3 +168,21 @@ impl Scope {
         minimal_unreachable!();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_accessors() {
+        let scope = Scope;
+        assert_eq!(scope.level(), None);
+        assert_eq!(scope.transaction(), None);
+        assert_eq!(scope.user(), None);
+        assert_eq!(scope.tags().count(), 0);
+        assert_eq!(scope.extra().count(), 0);
+        assert_eq!(scope.contexts().count(), 0);
+        assert_eq!(scope.breadcrumbs().count(), 0);
+        assert!(scope.get_span().is_none());
+    }
+}
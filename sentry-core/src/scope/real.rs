@@ -1,9 +1,11 @@
 use std::borrow::Cow;
 use std::fmt;
-use std::sync::{Arc, PoisonError, RwLock};
+use std::sync::{Arc, Mutex, PoisonError, RwLock};
 
 use crate::client::Client;
-use crate::protocol::{Breadcrumb, Context, Event, Level, User, Value};
+use crate::performance::TransactionOrSpan;
+use crate::protocol::{Attachment, Breadcrumb, Context, EnvelopeItem, Event, Level, User, Value};
+use crate::session::Session;
 
 #[derive(Debug)]
 pub struct Stack {
@@ -25,8 +27,9 @@ pub type EventProcessor = Box<dyn Fn(Event<'static>) -> Option<Event<'static>> +
 /// 2. the topmost scope can also be configured through the `configure_scope`
 ///    method.
 ///
-/// Note that the scope can only be modified but not inspected.  Only the
-/// client can use the scope to extract information currently.
+/// The data of the scope can be read back with accessors such as `tags` or
+/// `user`.  `Hub::scope_snapshot` returns the current scope of a hub without
+/// copying any of its data.
 #[derive(Clone)]
 pub struct Scope {
     pub(crate) level: Option<Level>,
@@ -38,6 +41,9 @@ pub struct Scope {
     pub(crate) tags: im::HashMap<String, String>,
     pub(crate) contexts: im::HashMap<String, Context>,
     pub(crate) event_processors: im::Vector<Arc<EventProcessor>>,
+    pub(crate) span: Arc<Option<TransactionOrSpan>>,
+    pub(crate) session: Arc<Mutex<Option<Session>>>,
+    pub(crate) attachments: im::Vector<Arc<Attachment>>,
 }
 
 impl fmt::Debug for Scope {
@@ -52,6 +58,9 @@ impl fmt::Debug for Scope {
             .field("tags", &self.tags)
             .field("contexts", &self.contexts)
             .field("event_processors", &self.event_processors.len())
+            .field("span", &self.span)
+            .field("session", &self.session)
+            .field("attachments", &self.attachments)
             .finish()
     }
 }
@@ -68,6 +77,9 @@ impl Default for Scope {
             tags: Default::default(),
             contexts: Default::default(),
             event_processors: Default::default(),
+            span: Default::default(),
+            session: Default::default(),
+            attachments: Default::default(),
         }
     }
 }
@@ -195,6 +207,70 @@ impl Scope {
         self.extra.remove(key);
     }
 
+    /// Returns the level override.
+    pub fn level(&self) -> Option<Level> {
+        self.level
+    }
+
+    /// Returns the transaction.
+    pub fn transaction(&self) -> Option<&str> {
+        self.transaction.as_ref().map(|txn| txn.as_str())
+    }
+
+    /// Returns the user of the scope.
+    pub fn user(&self) -> Option<&User> {
+        self.user.as_deref()
+    }
+
+    /// Returns an iterator over the tags.
+    pub fn tags(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.tags
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    /// Returns an iterator over the extra values.
+    pub fn extra(&self) -> impl Iterator<Item = (&str, &Value)> {
+        self.extra.iter().map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Returns an iterator over the contexts.
+    pub fn contexts(&self) -> impl Iterator<Item = (&str, &Context)> {
+        self.contexts
+            .iter()
+            .map(|(key, value)| (key.as_str(), value))
+    }
+
+    /// Returns an iterator over the breadcrumbs, oldest first.
+    pub fn breadcrumbs(&self) -> impl DoubleEndedIterator<Item = &Breadcrumb> + ExactSizeIterator {
+        self.breadcrumbs.iter()
+    }
+
+    /// Sets the currently active transaction or span.
+    ///
+    /// Events captured while a span is active are linked to its trace.
+    pub fn set_span(&mut self, span: Option<TransactionOrSpan>) {
+        self.span = Arc::new(span);
+    }
+
+    /// Returns the currently active transaction or span.
+    pub fn get_span(&self) -> Option<TransactionOrSpan> {
+        (*self.span).clone()
+    }
+
+    /// Adds an attachment to the scope.
+    ///
+    /// The attachment is sent along with every event captured while the
+    /// scope is active.
+    pub fn add_attachment(&mut self, attachment: Attachment) {
+        self.attachments.push_back(Arc::new(attachment));
+    }
+
+    /// Clears all attachments from the scope.
+    pub fn clear_attachments(&mut self) {
+        self.attachments.clear();
+    }
+
     /// Add an event processor to the scope.
     pub fn add_event_processor(
         &mut self,
@@ -203,6 +279,17 @@ impl Scope {
         self.event_processors.push_back(Arc::new(f));
     }
 
+    /// Updates the session of the scope from a captured event.
+    ///
+    /// Returns the envelope item with the new session state if the session
+    /// changed.
+    pub(crate) fn update_session_from_event(&self, event: &Event<'static>) -> Option<EnvelopeItem> {
+        let mut session = self.session.lock().unwrap();
+        let session = session.as_mut()?;
+        session.update_from_event(event);
+        session.create_envelope_item()
+    }
+
     /// Applies the contained scoped data to fill an event.
     #[allow(clippy::cognitive_complexity)]
     pub fn apply_to_event(&self, mut event: Event<'static>) -> Option<Event<'static>> {
@@ -222,6 +309,10 @@ impl Scope {
         event.tags.extend(self.tags.iter().cloned());
         event.contexts.extend(self.contexts.iter().cloned());
 
+        if let Some(ref span) = *self.span {
+            span.apply_to_event(&mut event);
+        }
+
         if event.transaction.is_none() {
             if let Some(ref txn) = self.transaction {
                 event.transaction = Some((**txn).clone());
@@ -0,0 +1,267 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use crate::client::Client;
+use crate::clientoptions::SessionMode;
+use crate::internals::{DateTime, TimeZone, Utc, Uuid};
+use crate::protocol::{
+    EnvelopeItem, Event, Level, SessionAggregateItem, SessionAggregates, SessionAttributes,
+    SessionStatus, SessionUpdate,
+};
+use crate::scope::StackLayer;
+use crate::transport::Transport;
+
+/// The interval in which aggregated sessions are sent.
+const FLUSH_INTERVAL: Duration = Duration::from_secs(60);
+
+/// A release health session.
+///
+/// The session is bound to the scope it was started on.  When the session is
+/// dropped it is closed and its final state is sent to Sentry.
+#[derive(Debug)]
+pub struct Session {
+    client: Arc<Client>,
+    session_update: SessionUpdate<'static>,
+    started: Instant,
+    dirty: bool,
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        self.close();
+        if self.is_request_mode() {
+            self.client.enqueue_session(&self.session_update);
+        } else if let Some(item) = self.create_envelope_item() {
+            self.client.send_envelope(item.into());
+        }
+    }
+}
+
+impl Session {
+    /// Creates a new session for the client and scope of the stack layer.
+    ///
+    /// Sessions are only created if the client is enabled and has a release
+    /// configured, as sessions without a release are rejected by Sentry.
+    pub fn from_stack(stack: &StackLayer) -> Option<Self> {
+        let client = stack.client.as_ref()?;
+        if !client.is_enabled() {
+            return None;
+        }
+        let options = client.options();
+        let distinct_id = stack.scope.user.as_ref().and_then(|user| {
+            user.id
+                .as_ref()
+                .or_else(|| user.email.as_ref())
+                .or_else(|| user.username.as_ref())
+                .cloned()
+        });
+        Some(Self {
+            client: client.clone(),
+            session_update: SessionUpdate {
+                session_id: Uuid::new_v4(),
+                distinct_id,
+                sequence: None,
+                timestamp: None,
+                started: Utc::now(),
+                init: true,
+                duration: None,
+                status: SessionStatus::Ok,
+                errors: 0,
+                attributes: SessionAttributes {
+                    release: options.release.clone()?,
+                    environment: options.environment.clone(),
+                    ip_address: None,
+                    user_agent: None,
+                },
+            },
+            started: Instant::now(),
+            dirty: true,
+        })
+    }
+
+    /// Updates the session status from a captured event.
+    ///
+    /// Events with an unhandled exception crash the session, other events
+    /// with an exception or an error level mark it as errored.
+    pub fn update_from_event(&mut self, event: &Event<'static>) {
+        match self.session_update.status {
+            SessionStatus::Ok | SessionStatus::Errored => {}
+            // a session that has already transitioned to a terminal state
+            // should not receive any more updates
+            _ => return,
+        }
+        let has_error = event.level >= Level::Error || !event.exception.values.is_empty();
+        let is_crash = event.exception.values.iter().any(|exception| {
+            exception
+                .mechanism
+                .as_ref()
+                .map_or(false, |mechanism| mechanism.handled == Some(false))
+        });
+
+        if is_crash {
+            self.session_update.status = SessionStatus::Crashed;
+        } else if has_error {
+            self.session_update.status = SessionStatus::Errored;
+        } else {
+            return;
+        }
+        self.session_update.errors += 1;
+        self.dirty = true;
+    }
+
+    /// Closes the session.
+    ///
+    /// Healthy sessions are marked as exited, errored and crashed sessions
+    /// keep their status.  The final state is always sent.
+    pub fn close(&mut self) {
+        if self.session_update.status == SessionStatus::Ok {
+            self.session_update.status = SessionStatus::Exited;
+        }
+        self.dirty = true;
+    }
+
+    /// Creates an envelope item with the current state of the session.
+    ///
+    /// Returns `None` if the session has not changed since it was last sent.
+    /// Request-mode sessions are only sent in aggregated form and never
+    /// create an item.
+    pub fn create_envelope_item(&mut self) -> Option<EnvelopeItem> {
+        if !self.dirty || self.is_request_mode() {
+            return None;
+        }
+        let duration = self.started.elapsed();
+        self.session_update.duration =
+            Some(duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1e9);
+        self.session_update.timestamp = Some(Utc::now());
+        let item = self.session_update.clone().into();
+        self.session_update.init = false;
+        self.dirty = false;
+        Some(item)
+    }
+
+    fn is_request_mode(&self) -> bool {
+        self.client.options().session_mode == SessionMode::Request
+    }
+}
+
+type AggregateKey = (DateTime<Utc>, Option<String>);
+
+type AggregateQueue = Arc<Mutex<HashMap<AggregateKey, SessionAggregateItem>>>;
+
+/// Aggregates closed request-mode sessions and periodically sends them.
+///
+/// Sessions are counted in buckets per minute they started in.  A background
+/// thread sends the counts every minute, the remaining counts are sent when
+/// the flusher is dropped.
+pub struct SessionFlusher {
+    transport: Arc<dyn Transport>,
+    attributes: SessionAttributes<'static>,
+    queue: AggregateQueue,
+    shutdown: Arc<(Mutex<bool>, Condvar)>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl SessionFlusher {
+    /// Creates a new flusher sending to the given transport.
+    pub fn new(transport: Arc<dyn Transport>, attributes: SessionAttributes<'static>) -> Self {
+        let queue = AggregateQueue::default();
+        let shutdown = Arc::new((Mutex::new(false), Condvar::new()));
+
+        let worker = {
+            let transport = transport.clone();
+            let attributes = attributes.clone();
+            let queue = queue.clone();
+            let shutdown = shutdown.clone();
+            thread::Builder::new()
+                .name("sentry-session-flusher".into())
+                .spawn(move || {
+                    let (lock, cvar) = &*shutdown;
+                    let mut shutdown = lock.lock().unwrap();
+                    let mut last_flush = Instant::now();
+                    while !*shutdown {
+                        let timeout = FLUSH_INTERVAL
+                            .checked_sub(last_flush.elapsed())
+                            .unwrap_or_default();
+                        shutdown = cvar.wait_timeout(shutdown, timeout).unwrap().0;
+                        if last_flush.elapsed() >= FLUSH_INTERVAL {
+                            flush_queue(&queue, &attributes, &*transport);
+                            last_flush = Instant::now();
+                        }
+                    }
+                })
+                .ok()
+        };
+
+        SessionFlusher {
+            transport,
+            attributes,
+            queue,
+            shutdown,
+            worker,
+        }
+    }
+
+    /// Counts a closed session in its bucket.
+    pub fn enqueue(&self, session_update: &SessionUpdate<'static>) {
+        let timestamp = session_update.started.timestamp();
+        let started = Utc
+            .timestamp_opt(timestamp - timestamp.rem_euclid(60), 0)
+            .single()
+            .unwrap_or(session_update.started);
+        let key = (started, session_update.distinct_id.clone());
+
+        let mut queue = self.queue.lock().unwrap();
+        let item = queue.entry(key).or_insert_with(|| SessionAggregateItem {
+            started,
+            distinct_id: session_update.distinct_id.clone(),
+            exited: 0,
+            errored: 0,
+            abnormal: 0,
+            crashed: 0,
+        });
+        match session_update.status {
+            SessionStatus::Ok | SessionStatus::Exited => item.exited += 1,
+            SessionStatus::Errored => item.errored += 1,
+            SessionStatus::Abnormal => item.abnormal += 1,
+            SessionStatus::Crashed => item.crashed += 1,
+        }
+    }
+
+    /// Sends the current counts immediately.
+    pub fn flush(&self) {
+        flush_queue(&self.queue, &self.attributes, &*self.transport);
+    }
+}
+
+impl Drop for SessionFlusher {
+    fn drop(&mut self) {
+        let (lock, cvar) = &*self.shutdown;
+        *lock.lock().unwrap() = true;
+        cvar.notify_one();
+        if let Some(worker) = self.worker.take() {
+            worker.join().ok();
+        }
+        self.flush();
+    }
+}
+
+fn flush_queue(
+    queue: &AggregateQueue,
+    attributes: &SessionAttributes<'static>,
+    transport: &dyn Transport,
+) {
+    let aggregates: Vec<_> = {
+        let mut queue = queue.lock().unwrap();
+        queue.drain().map(|(_, item)| item).collect()
+    };
+    if aggregates.is_empty() {
+        return;
+    }
+    let aggregates = SessionAggregates {
+        aggregates,
+        attributes: attributes.clone(),
+    };
+    transport.send_envelope(aggregates.into());
+}
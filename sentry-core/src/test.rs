@@ -19,19 +19,43 @@
 //! assert_eq!(events.len(), 1);
 //! assert_eq!(events[0].message.as_ref().unwrap(), "Hello World!");
 //! ```
+//!
+//! If the test needs to look at the complete envelopes that were handed to
+//! the transport, `with_captured_envelopes` can be used instead.
+//!
+//! To test the HTTP transports themselves, the `MockServer` acts as a Sentry
+//! server on localhost that records requests and answers them with scripted
+//! responses.
 use std::sync::{Arc, Mutex};
 
 use crate::client::ClientOptions;
 use crate::hub::Hub;
 use crate::internals::Dsn;
-use crate::protocol::Event;
+use crate::protocol::{Envelope, EnvelopeItem, Event};
 use crate::transport::Transport;
 
+#[cfg(feature = "with_test_support")]
+mod mock_server;
+
+#[cfg(feature = "with_test_support")]
+pub use self::mock_server::{MockRequest, MockResponse, MockServer};
+
 lazy_static::lazy_static! {
     static ref TEST_DSN: Dsn = "https://public@sentry.invalid/1".parse().unwrap();
 }
 
-/// Collects events instead of sending them.
+fn events_from_envelopes(envelopes: Vec<Envelope>) -> Vec<Event<'static>> {
+    envelopes
+        .into_iter()
+        .flat_map(Envelope::into_items)
+        .filter_map(|item| match item {
+            EnvelopeItem::Event(event) => Some(event),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Collects envelopes instead of sending them.
 ///
 /// Example usage:
 ///
@@ -50,7 +74,7 @@ lazy_static::lazy_static! {
 /// Hub::current().bind_client(Some(Arc::new(options.into())));
 /// ```
 pub struct TestTransport {
-    collected: Mutex<Vec<Event<'static>>>,
+    collected: Mutex<Vec<Envelope>>,
 }
 
 impl TestTransport {
@@ -63,7 +87,14 @@ impl TestTransport {
     }
 
     /// Fetches and clears the contained events.
+    ///
+    /// All envelope items other than events are discarded.
     pub fn fetch_and_clear_events(&self) -> Vec<Event<'static>> {
+        events_from_envelopes(self.fetch_and_clear_envelopes())
+    }
+
+    /// Fetches and clears the contained envelopes.
+    pub fn fetch_and_clear_envelopes(&self) -> Vec<Envelope> {
         let mut guard = self.collected.lock().unwrap();
         std::mem::replace(&mut *guard, vec![])
     }
@@ -71,7 +102,11 @@ impl TestTransport {
 
 impl Transport for TestTransport {
     fn send_event(&self, event: Event<'static>) {
-        self.collected.lock().unwrap().push(event);
+        self.send_envelope(event.into())
+    }
+
+    fn send_envelope(&self, envelope: Envelope) {
+        self.collected.lock().unwrap().push(envelope);
     }
 }
 
@@ -95,6 +130,25 @@ pub fn with_captured_events_options<F: FnOnce(), O: Into<ClientOptions>>(
     f: F,
     options: O,
 ) -> Vec<Event<'static>> {
+    events_from_envelopes(with_captured_envelopes_options(f, options))
+}
+
+/// Runs some code with the default test hub and returns the captured
+/// envelopes.
+///
+/// This is the envelope based equivalent of `with_captured_events`.
+pub fn with_captured_envelopes<F: FnOnce()>(f: F) -> Vec<Envelope> {
+    with_captured_envelopes_options(f, ClientOptions::default())
+}
+
+/// Runs some code with the default test hub with the given options and
+/// returns the captured envelopes.
+///
+/// This is the envelope based equivalent of `with_captured_events_options`.
+pub fn with_captured_envelopes_options<F: FnOnce(), O: Into<ClientOptions>>(
+    f: F,
+    options: O,
+) -> Vec<Envelope> {
     let transport = TestTransport::new();
     let mut options = options.into();
     options.dsn = Some(options.dsn.unwrap_or_else(|| TEST_DSN.clone()));
@@ -106,5 +160,5 @@ pub fn with_captured_events_options<F: FnOnce(), O: Into<ClientOptions>>(
         )),
         f,
     );
-    transport.fetch_and_clear_events()
+    transport.fetch_and_clear_envelopes()
 }
@@ -0,0 +1,364 @@
+use std::collections::VecDeque;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::internals::{Auth, Dsn};
+use crate::protocol::Envelope;
+
+/// A response the `MockServer` answers a request with.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockResponse {
+    /// The status code of the response.
+    pub status: u16,
+    /// The headers of the response.
+    pub headers: Vec<(String, String)>,
+    /// The body of the response.
+    pub body: Vec<u8>,
+}
+
+impl MockResponse {
+    /// Creates an empty response with the given status code.
+    pub fn new(status: u16) -> MockResponse {
+        MockResponse {
+            status,
+            headers: vec![],
+            body: vec![],
+        }
+    }
+
+    /// Creates a `429 Too Many Requests` response with a `Retry-After` header.
+    pub fn rate_limited(retry_after: Duration) -> MockResponse {
+        MockResponse::new(429).with_header("Retry-After", &retry_after.as_secs().to_string())
+    }
+
+    /// Adds a header to the response.
+    pub fn with_header(mut self, name: &str, value: &str) -> MockResponse {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Sets the body of the response.
+    pub fn with_body<B: Into<Vec<u8>>>(mut self, body: B) -> MockResponse {
+        self.body = body.into();
+        self
+    }
+}
+
+impl Default for MockResponse {
+    fn default() -> MockResponse {
+        MockResponse::new(200).with_body("{}")
+    }
+}
+
+/// A request received by the `MockServer`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct MockRequest {
+    /// The request method.
+    pub method: String,
+    /// The path of the request including the query string.
+    pub path: String,
+    /// The request headers in the order they were sent.
+    pub headers: Vec<(String, String)>,
+    /// The request body as it was sent.
+    pub body: Vec<u8>,
+}
+
+impl MockRequest {
+    /// Returns the value of the first header with the given name.
+    ///
+    /// Header names are compared case insensitively.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Parses the `X-Sentry-Auth` header.
+    pub fn auth(&self) -> Option<Auth> {
+        self.header("X-Sentry-Auth")?.parse().ok()
+    }
+
+    /// Returns the `Content-Encoding` of the body.
+    pub fn content_encoding(&self) -> Option<&str> {
+        self.header("Content-Encoding")
+    }
+
+    /// Returns the body with the content encoding removed.
+    ///
+    /// Supports `gzip` and `deflate`.  Returns `None` for other encodings or
+    /// bodies that fail to decode.
+    pub fn decoded_body(&self) -> Option<Vec<u8>> {
+        use miniz_oxide::inflate::{decompress_to_vec, decompress_to_vec_zlib};
+
+        match self.content_encoding() {
+            None | Some("identity") => Some(self.body.clone()),
+            Some("deflate") => decompress_to_vec_zlib(&self.body).ok(),
+            // only gzip members without optional header fields are supported
+            Some("gzip") if self.body.len() >= 18 && self.body[3] == 0 => {
+                decompress_to_vec(&self.body[10..self.body.len() - 8]).ok()
+            }
+            Some(_) => None,
+        }
+    }
+
+    /// Parses the decoded body as an envelope.
+    pub fn envelope(&self) -> Option<Envelope> {
+        Envelope::from_slice(&self.decoded_body()?).ok()
+    }
+}
+
+#[derive(Default)]
+struct State {
+    requests: Vec<MockRequest>,
+    responses: VecDeque<MockResponse>,
+    default_response: MockResponse,
+}
+
+struct Shared {
+    state: Mutex<State>,
+    received: Condvar,
+    stopped: AtomicBool,
+}
+
+/// A fake Sentry server for integration tests.
+///
+/// The server listens on a random port on localhost and records every request
+/// it receives.  Requests are answered with scripted responses, which makes it
+/// possible to test transports end to end, including their handling of rate
+/// limits and server errors.
+///
+/// # Example usage
+///
+/// ```
+/// # use sentry_core as sentry;
+/// use std::time::Duration;
+/// use sentry::test::{MockResponse, MockServer};
+///
+/// let server = MockServer::start();
+/// server.respond_with(MockResponse::rate_limited(Duration::from_secs(60)));
+///
+/// let options = sentry::ClientOptions {
+///     dsn: Some(server.dsn()),
+///     ..Default::default()
+/// };
+/// ```
+///
+/// The server stops when it is dropped.
+pub struct MockServer {
+    dsn: Dsn,
+    shared: Arc<Shared>,
+}
+
+impl MockServer {
+    /// Starts a new server on a random port.
+    pub fn start() -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("failed to bind mock server");
+        let port = listener.local_addr().unwrap().port();
+        let shared = Arc::new(Shared {
+            state: Default::default(),
+            received: Condvar::new(),
+            stopped: AtomicBool::new(false),
+        });
+
+        let server_shared = shared.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                if server_shared.stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                if let Ok(stream) = stream {
+                    let shared = server_shared.clone();
+                    thread::spawn(move || serve_connection(stream, &shared));
+                }
+            }
+        });
+
+        MockServer {
+            dsn: format!("http://public@127.0.0.1:{}/1", port)
+                .parse()
+                .unwrap(),
+            shared,
+        }
+    }
+
+    /// Returns a DSN pointing to the server.
+    pub fn dsn(&self) -> Dsn {
+        self.dsn.clone()
+    }
+
+    /// Answers the next request with the given response.
+    ///
+    /// Scripted responses are used in the order they were added.  Once they
+    /// are used up requests are answered with the default response.
+    pub fn respond_with(&self, response: MockResponse) {
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .responses
+            .push_back(response);
+    }
+
+    /// Sets the response used once all scripted responses are used up.
+    ///
+    /// This is a `200 OK` response by default.
+    pub fn set_default_response(&self, response: MockResponse) {
+        self.shared.state.lock().unwrap().default_response = response;
+    }
+
+    /// Returns all requests received so far.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        self.shared.state.lock().unwrap().requests.clone()
+    }
+
+    /// Fetches and clears the received requests.
+    pub fn fetch_and_clear_requests(&self) -> Vec<MockRequest> {
+        std::mem::take(&mut self.shared.state.lock().unwrap().requests)
+    }
+
+    /// Waits until at least `count` requests were received.
+    ///
+    /// Returns `false` if the timeout elapsed before.
+    pub fn wait_for_requests(&self, count: usize, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.shared.state.lock().unwrap();
+        while state.requests.len() < count {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            state = self
+                .shared
+                .received
+                .wait_timeout(state, deadline - now)
+                .unwrap()
+                .0;
+        }
+        true
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shared.stopped.store(true, Ordering::SeqCst);
+        // wake up the accept loop so it notices the server was stopped
+        let addr = (self.dsn.host(), self.dsn.port());
+        TcpStream::connect(addr).ok();
+    }
+}
+
+fn serve_connection(stream: TcpStream, shared: &Shared) {
+    let mut reader = BufReader::new(match stream.try_clone() {
+        Ok(stream) => stream,
+        Err(_) => return,
+    });
+    let mut writer = stream;
+    // keep-alive connections are served until the client closes them
+    while let Ok(Some(request)) = read_request(&mut reader) {
+        let response = {
+            let mut state = shared.state.lock().unwrap();
+            state.requests.push(request);
+            shared.received.notify_all();
+            state
+                .responses
+                .pop_front()
+                .unwrap_or_else(|| state.default_response.clone())
+        };
+        if write_response(&mut writer, &response).is_err() {
+            break;
+        }
+    }
+    writer.shutdown(Shutdown::Both).ok();
+}
+
+fn read_request<R: BufRead>(reader: &mut R) -> io::Result<Option<MockRequest>> {
+    let mut line = String::new();
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+    let mut parts = line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers = vec![];
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let header = line.trim_end();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(idx) = header.find(':') {
+            headers.push((
+                header[..idx].trim().to_string(),
+                header[idx + 1..].trim().to_string(),
+            ));
+        }
+    }
+
+    let mut request = MockRequest {
+        method,
+        path,
+        headers,
+        body: vec![],
+    };
+    if request
+        .header("Transfer-Encoding")
+        .map_or(false, |value| value.eq_ignore_ascii_case("chunked"))
+    {
+        request.body = read_chunked(reader)?;
+    } else if let Some(length) = request.header("Content-Length") {
+        let length = length
+            .parse()
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid content length"))?;
+        request.body.resize(length, 0);
+        reader.read_exact(&mut request.body)?;
+    }
+    Ok(Some(request))
+}
+
+fn read_chunked<R: BufRead>(reader: &mut R) -> io::Result<Vec<u8>> {
+    let mut body = vec![];
+    let mut line = String::new();
+    loop {
+        line.clear();
+        reader.read_line(&mut line)?;
+        let size = line.trim().split(';').next().unwrap_or_default();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "invalid chunk size"))?;
+        if size == 0 {
+            // skip the trailer
+            loop {
+                line.clear();
+                if reader.read_line(&mut line)? == 0 || line.trim_end().is_empty() {
+                    return Ok(body);
+                }
+            }
+        }
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        line.clear();
+        reader.read_line(&mut line)?;
+    }
+}
+
+fn write_response<W: Write>(writer: &mut W, response: &MockResponse) -> io::Result<()> {
+    let mut head = format!(
+        "HTTP/1.1 {} Mock\r\ncontent-length: {}\r\n",
+        response.status,
+        response.body.len()
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str("\r\n");
+    writer.write_all(head.as_bytes())?;
+    writer.write_all(&response.body)?;
+    writer.flush()
+}
@@ -1,7 +1,8 @@
 use std::sync::Arc;
 use std::time::Duration;
 
-use crate::protocol::Event;
+use crate::client_reports::ClientReportRecorder;
+use crate::protocol::{Envelope, EnvelopeItem, Event};
 use crate::ClientOptions;
 
 /// The trait for transports.
@@ -9,10 +10,37 @@ use crate::ClientOptions;
 /// A transport is responsible for sending events to Sentry.  Custom implementations
 /// can be created to use a different abstraction to send events.  This is for instance
 /// used for the test system.
+///
+/// The client hands all data to the transport as envelopes.  Transports only
+/// able to deal with events need to implement `send_event` alone, in which
+/// case all envelope items other than events are dropped.
 pub trait Transport: Send + Sync + 'static {
     /// Sends an event.
     fn send_event(&self, event: Event<'static>);
 
+    /// Sends an envelope.
+    ///
+    /// The default implementation forwards the events contained in the
+    /// envelope to `send_event` and drops all other items.
+    fn send_envelope(&self, envelope: Envelope) {
+        for item in envelope.into_items() {
+            if let EnvelopeItem::Event(event) = item {
+                self.send_event(event);
+            }
+        }
+    }
+
+    /// Waits until all envelopes queued so far have been sent.
+    ///
+    /// Unlike `shutdown` the transport remains usable afterwards.  The
+    /// default implementation does nothing.  The return value should be
+    /// `true` if the queue was drained in time or `false` if envelopes were
+    /// left in it.
+    fn flush(&self, timeout: Duration) -> bool {
+        let _timeout = timeout;
+        true
+    }
+
     /// Drains the queue if there is one.
     ///
     /// The default implementation does nothing.  If the queue was successfully
@@ -22,6 +50,16 @@ pub trait Transport: Send + Sync + 'static {
         let _timeout = timeout;
         true
     }
+
+    /// Returns the recorder in which the transport counts discarded data.
+    ///
+    /// The client records the data it discards itself in the same recorder
+    /// and periodically sends the counts to Sentry.  The default
+    /// implementation returns `None`, in which case the client uses a
+    /// recorder of its own.
+    fn client_reports(&self) -> Option<Arc<ClientReportRecorder>> {
+        None
+    }
 }
 
 /// A factory creating transport instances.
@@ -56,9 +94,21 @@ impl<T: Transport> Transport for Arc<T> {
         (**self).send_event(event)
     }
 
+    fn send_envelope(&self, envelope: Envelope) {
+        (**self).send_envelope(envelope)
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        (**self).flush(timeout)
+    }
+
     fn shutdown(&self, timeout: Duration) -> bool {
         (**self).shutdown(timeout)
     }
+
+    fn client_reports(&self) -> Option<Arc<ClientReportRecorder>> {
+        (**self).client_reports()
+    }
 }
 
 impl<T: Transport> TransportFactory for Arc<T> {
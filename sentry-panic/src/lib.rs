@@ -23,7 +23,7 @@ use std::panic::{self, PanicInfo};
 use std::sync::Once;
 
 use sentry_backtrace::current_stacktrace;
-use sentry_core::protocol::{Event, Exception, Level};
+use sentry_core::protocol::{Event, Exception, Level, Mechanism};
 use sentry_core::{ClientOptions, Hub, Integration};
 
 /// A panic handler that sends to Sentry.
@@ -93,29 +93,41 @@ impl PanicIntegration {
 
     /// Creates an event from the given panic info.
     ///
-    /// The stacktrace is calculated from the current frame.
+    /// The stacktrace is calculated from the current frame.  The exceptions
+    /// of the event are marked as unhandled, which lets release health count
+    /// the session as crashed.
     pub fn event_from_panic_info(&self, info: &PanicInfo<'_>) -> Event<'static> {
-        for extractor in &self.extractors {
-            if let Some(event) = extractor(info) {
-                return event;
-            }
-        }
+        let mut event = self
+            .extractors
+            .iter()
+            .find_map(|extractor| extractor(info))
+            .unwrap_or_else(|| {
+                // TODO: We would ideally want to downcast to `std::error:Error` here
+                // and use `event_from_error`, but that way we won‘t get meaningful
+                // backtraces yet.
 
-        // TODO: We would ideally want to downcast to `std::error:Error` here
-        // and use `event_from_error`, but that way we won‘t get meaningful
-        // backtraces yet.
+                let msg = message_from_panic_info(info);
+                Event {
+                    exception: vec![Exception {
+                        ty: "panic".into(),
+                        value: Some(msg.to_string()),
+                        stacktrace: current_stacktrace(),
+                        ..Default::default()
+                    }]
+                    .into(),
+                    level: Level::Fatal,
+                    ..Default::default()
+                }
+            });
 
-        let msg = message_from_panic_info(info);
-        Event {
-            exception: vec![Exception {
+        for exception in event.exception.values.iter_mut() {
+            exception.mechanism.get_or_insert_with(|| Mechanism {
                 ty: "panic".into(),
-                value: Some(msg.to_string()),
-                stacktrace: current_stacktrace(),
+                handled: Some(false),
                 ..Default::default()
-            }]
-            .into(),
-            level: Level::Fatal,
-            ..Default::default()
+            });
         }
+
+        event
     }
 }
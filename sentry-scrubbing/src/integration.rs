@@ -0,0 +1,282 @@
+use sentry_core::protocol::{Context, Event, Stacktrace, Value};
+use sentry_core::{ClientOptions, Integration};
+
+use crate::utils::{normalize_key, Scrubber, FILTERED};
+
+/// The keys masked by default.
+///
+/// Keys are matched if they contain one of these entries.
+pub const DEFAULT_DENYLIST: &[&str] = &[
+    "password",
+    "passwd",
+    "secret",
+    "api_key",
+    "apikey",
+    "auth",
+    "credentials",
+    "token",
+    "private_key",
+    "privatekey",
+    "session",
+    "cookie",
+    "csrf",
+    "xsrf",
+];
+
+/// Scrubs sensitive data from Sentry Events.
+///
+/// Values under keys matching the `denylist` are replaced with `[Filtered]`,
+/// as are credit card numbers and IBANs found in string values.  This
+/// applies to `extra`, `contexts`, the request headers, query string,
+/// cookies, data and env, breadcrumb data, the local variables of frames and
+/// the user.
+pub struct DataScrubbingIntegration {
+    /// The keys whose values are masked, see `DEFAULT_DENYLIST`.
+    ///
+    /// Keys are matched if they contain one of the entries, ignoring case and
+    /// treating dashes as underscores.
+    pub denylist: Vec<String>,
+    /// Mask credit card numbers, enabled by default.
+    pub scrub_credit_cards: bool,
+    /// Mask IBANs, enabled by default.
+    pub scrub_ibans: bool,
+}
+
+impl Default for DataScrubbingIntegration {
+    fn default() -> Self {
+        Self {
+            denylist: DEFAULT_DENYLIST.iter().map(|key| key.to_string()).collect(),
+            scrub_credit_cards: true,
+            scrub_ibans: true,
+        }
+    }
+}
+
+impl DataScrubbingIntegration {
+    /// Creates a new Data Scrubbing Integration.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a key to the denylist.
+    pub fn add_denied_key<K: Into<String>>(mut self, key: K) -> Self {
+        self.denylist.push(key.into());
+        self
+    }
+
+    fn scrubber(&self) -> Scrubber {
+        Scrubber {
+            denylist: self.denylist.iter().map(|key| normalize_key(key)).collect(),
+            credit_cards: self.scrub_credit_cards,
+            ibans: self.scrub_ibans,
+        }
+    }
+}
+
+impl Integration for DataScrubbingIntegration {
+    fn name(&self) -> &'static str {
+        "data-scrubbing"
+    }
+
+    fn process_event(
+        &self,
+        mut event: Event<'static>,
+        _cfg: &ClientOptions,
+    ) -> Option<Event<'static>> {
+        let scrubber = self.scrubber();
+
+        scrubber.scrub_map(&mut event.extra);
+        for context in event.contexts.values_mut() {
+            scrub_context(&scrubber, context);
+        }
+
+        if let Some(ref mut request) = event.request {
+            scrubber.scrub_string_map(&mut request.headers);
+            scrubber.scrub_string_map(&mut request.env);
+            if let Some(ref mut query) = request.query_string {
+                scrubber.scrub_pairs(query, '&');
+            }
+            if let Some(ref mut cookies) = request.cookies {
+                scrubber.scrub_pairs(cookies, ';');
+            }
+            if let Some(ref mut data) = request.data {
+                scrub_data(&scrubber, data);
+            }
+        }
+
+        for breadcrumb in event.breadcrumbs.iter_mut() {
+            scrubber.scrub_map(&mut breadcrumb.data);
+        }
+
+        scrub_stacktrace(&scrubber, event.stacktrace.as_mut());
+        for exception in event.exception.iter_mut() {
+            scrub_stacktrace(&scrubber, exception.stacktrace.as_mut());
+        }
+        for thread in event.threads.iter_mut() {
+            scrub_stacktrace(&scrubber, thread.stacktrace.as_mut());
+        }
+
+        if let Some(ref mut user) = event.user {
+            scrub_field(&scrubber, "id", &mut user.id);
+            scrub_field(&scrubber, "email", &mut user.email);
+            scrub_field(&scrubber, "username", &mut user.username);
+            if scrubber.is_denied("ip_address") {
+                user.ip_address = None;
+            }
+            scrubber.scrub_map(&mut user.other);
+        }
+
+        Some(event)
+    }
+}
+
+fn scrub_field(scrubber: &Scrubber, key: &str, value: &mut Option<String>) {
+    if let Some(ref mut value) = *value {
+        if scrubber.is_denied(key) {
+            *value = FILTERED.into();
+        } else {
+            scrubber.scrub_str(value);
+        }
+    }
+}
+
+fn scrub_context(scrubber: &Scrubber, context: &mut Context) {
+    match *context {
+        Context::Device(ref mut context) => scrubber.scrub_map(&mut context.other),
+        Context::Os(ref mut context) => scrubber.scrub_map(&mut context.other),
+        Context::Runtime(ref mut context) => scrubber.scrub_map(&mut context.other),
+        Context::App(ref mut context) => scrubber.scrub_map(&mut context.other),
+        Context::Browser(ref mut context) => scrubber.scrub_map(&mut context.other),
+        Context::Other(ref mut map) => scrubber.scrub_map(map),
+        _ => {}
+    }
+}
+
+fn scrub_stacktrace(scrubber: &Scrubber, stacktrace: Option<&mut Stacktrace>) {
+    if let Some(stacktrace) = stacktrace {
+        for frame in &mut stacktrace.frames {
+            scrubber.scrub_map(&mut frame.vars);
+        }
+    }
+}
+
+/// Scrubs the request body, which is either JSON or form data.
+fn scrub_data(scrubber: &Scrubber, data: &mut String) {
+    match serde_json::from_str::<Value>(data) {
+        Ok(mut value) => {
+            scrubber.scrub_value(&mut value);
+            *data = value.to_string();
+        }
+        Err(_) => scrubber.scrub_pairs(data, '&'),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use sentry_core::protocol::{Breadcrumb, Frame, Map, Request, User};
+
+    fn map(pairs: &[(&str, Value)]) -> Map<String, Value> {
+        pairs
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_scrub_event() {
+        let event = Event {
+            extra: map(&[
+                ("db_password", "hunter2".into()),
+                ("card", "4111-1111-1111-1111".into()),
+                (
+                    "nested",
+                    serde_json::json!({"api_key": "abc", "ok": "fine"}),
+                ),
+            ]),
+            request: Some(Request {
+                headers: vec![
+                    ("Authorization".to_string(), "Bearer abc".to_string()),
+                    ("Accept".to_string(), "*/*".to_string()),
+                ]
+                .into_iter()
+                .collect(),
+                query_string: Some("page=1&token=abc".into()),
+                cookies: Some("theme=dark; sessionid=abc".into()),
+                data: Some(r#"{"user":{"password":"hunter2"}}"#.into()),
+                ..Default::default()
+            }),
+            breadcrumbs: vec![Breadcrumb {
+                data: map(&[("secret", "abc".into())]),
+                ..Default::default()
+            }]
+            .into(),
+            stacktrace: Some(Stacktrace {
+                frames: vec![Frame {
+                    vars: map(&[("password", "hunter2".into()), ("count", 1.into())]),
+                    ..Default::default()
+                }],
+                ..Default::default()
+            }),
+            user: Some(User {
+                username: Some("jane".into()),
+                other: map(&[("auth_token", "abc".into())]),
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+
+        let event = DataScrubbingIntegration::new()
+            .process_event(event, &Default::default())
+            .unwrap();
+
+        assert_eq!(event.extra["db_password"], FILTERED);
+        assert_eq!(event.extra["card"], FILTERED);
+        assert_eq!(
+            event.extra["nested"],
+            serde_json::json!({"api_key": FILTERED, "ok": "fine"})
+        );
+
+        let request = event.request.unwrap();
+        assert_eq!(request.headers["Authorization"], FILTERED);
+        assert_eq!(request.headers["Accept"], "*/*");
+        assert_eq!(request.query_string.unwrap(), "page=1&token=[Filtered]");
+        assert_eq!(request.cookies.unwrap(), "theme=dark; sessionid=[Filtered]");
+        assert_eq!(
+            request.data.unwrap(),
+            r#"{"user":{"password":"[Filtered]"}}"#
+        );
+
+        assert_eq!(event.breadcrumbs[0].data["secret"], FILTERED);
+        let vars = &event.stacktrace.unwrap().frames[0].vars;
+        assert_eq!(vars["password"], FILTERED);
+        assert_eq!(vars["count"], 1);
+
+        let user = event.user.unwrap();
+        assert_eq!(user.username.unwrap(), "jane");
+        assert_eq!(user.other["auth_token"], FILTERED);
+    }
+
+    #[test]
+    fn test_custom_denylist() {
+        let integration = DataScrubbingIntegration {
+            denylist: vec!["email".into()],
+            ..Default::default()
+        };
+        let event = Event {
+            user: Some(User {
+                email: Some("jane@example.com".into()),
+                ..Default::default()
+            }),
+            extra: map(&[("password", "hunter2".into())]),
+            ..Default::default()
+        };
+
+        let event = integration
+            .process_event(event, &Default::default())
+            .unwrap();
+        assert_eq!(event.user.unwrap().email.unwrap(), FILTERED);
+        assert_eq!(event.extra["password"], "hunter2");
+    }
+}
@@ -0,0 +1,22 @@
+//! Scrubs sensitive data from Sentry Events.
+//!
+//! The `DataScrubbingIntegration` masks the values of keys such as
+//! `password`, `secret` or `authorization` as well as credit card numbers and
+//! IBANs anywhere in the `extra` data, contexts, request, breadcrumbs, local
+//! variables and user of an Event before it is sent.
+//!
+//! # Examples
+//!
+//! ```
+//! let integration = sentry_scrubbing::DataScrubbingIntegration::new()
+//!     .add_denied_key("ssn");
+//! let _sentry = sentry::init(sentry::ClientOptions::default()
+//!     .add_integration(integration));
+//! ```
+
+#![deny(missing_docs)]
+
+mod integration;
+mod utils;
+
+pub use integration::{DataScrubbingIntegration, DEFAULT_DENYLIST};
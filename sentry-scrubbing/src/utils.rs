@@ -0,0 +1,222 @@
+use std::borrow::Cow;
+
+use lazy_static::lazy_static;
+use regex::{Captures, Regex};
+use sentry_core::protocol::{Map, Value};
+
+/// The value scrubbed data is replaced with.
+pub const FILTERED: &str = "[Filtered]";
+
+lazy_static! {
+    static ref CREDIT_CARD_RE: Regex = Regex::new(r"\b(?:\d[ -]?){12,18}\d\b").unwrap();
+    static ref IBAN_RE: Regex = Regex::new(r"\b[A-Z]{2}\d{2}(?: ?[A-Z0-9]){11,30}\b").unwrap();
+}
+
+/// Decides which data is scrubbed.
+///
+/// The denylist entries are expected to be normalized with `normalize_key`.
+pub struct Scrubber {
+    pub denylist: Vec<String>,
+    pub credit_cards: bool,
+    pub ibans: bool,
+}
+
+impl Scrubber {
+    /// Checks whether the values under a key have to be masked.
+    ///
+    /// Keys match if they contain one of the denylist entries, ignoring case
+    /// and treating dashes as underscores.
+    pub fn is_denied(&self, key: &str) -> bool {
+        let key = normalize_key(key);
+        self.denylist
+            .iter()
+            .any(|denied| key.contains(denied.as_str()))
+    }
+
+    /// Masks credit card numbers and IBANs in a string.
+    pub fn scrub_str(&self, value: &mut String) {
+        if let Cow::Owned(scrubbed) = self.scrub_patterns(value) {
+            *value = scrubbed;
+        }
+    }
+
+    fn scrub_patterns<'a>(&self, value: &'a str) -> Cow<'a, str> {
+        let mut value = Cow::Borrowed(value);
+        if self.credit_cards {
+            if let Cow::Owned(scrubbed) = replace_valid(&CREDIT_CARD_RE, &value, is_credit_card) {
+                value = Cow::Owned(scrubbed);
+            }
+        }
+        if self.ibans {
+            if let Cow::Owned(scrubbed) = replace_valid(&IBAN_RE, &value, is_iban) {
+                value = Cow::Owned(scrubbed);
+            }
+        }
+        value
+    }
+
+    /// Scrubs a JSON value, masking everything below denied keys.
+    pub fn scrub_value(&self, value: &mut Value) {
+        match *value {
+            Value::String(ref mut string) => self.scrub_str(string),
+            Value::Array(ref mut values) => {
+                for value in values {
+                    self.scrub_value(value);
+                }
+            }
+            Value::Object(ref mut map) => {
+                for (key, value) in map.iter_mut() {
+                    if self.is_denied(key) {
+                        *value = FILTERED.into();
+                    } else {
+                        self.scrub_value(value);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Scrubs a map of JSON values.
+    pub fn scrub_map(&self, map: &mut Map<String, Value>) {
+        for (key, value) in map.iter_mut() {
+            if self.is_denied(key) {
+                *value = FILTERED.into();
+            } else {
+                self.scrub_value(value);
+            }
+        }
+    }
+
+    /// Scrubs a map of strings.
+    pub fn scrub_string_map(&self, map: &mut Map<String, String>) {
+        for (key, value) in map.iter_mut() {
+            if self.is_denied(key) {
+                *value = FILTERED.into();
+            } else {
+                self.scrub_str(value);
+            }
+        }
+    }
+
+    /// Scrubs `key=value` pairs separated by `separator`.
+    ///
+    /// This is used for query strings, cookies and form data.
+    pub fn scrub_pairs(&self, pairs: &mut String, separator: char) {
+        let scrubbed: Vec<_> = pairs
+            .split(separator)
+            .map(|pair| {
+                let mut parts = pair.splitn(2, '=');
+                let key = parts.next().unwrap_or_default();
+                match parts.next() {
+                    Some(_) if self.is_denied(key.trim()) => format!("{}={}", key, FILTERED),
+                    _ => self.scrub_patterns(pair).into_owned(),
+                }
+            })
+            .collect();
+        *pairs = scrubbed.join(&separator.to_string());
+    }
+}
+
+pub fn normalize_key(key: &str) -> String {
+    key.to_lowercase().replace('-', "_")
+}
+
+fn replace_valid<'a>(regex: &Regex, value: &'a str, is_valid: fn(&str) -> bool) -> Cow<'a, str> {
+    regex.replace_all(value, |captures: &Captures| {
+        let matched = &captures[0];
+        if is_valid(matched) {
+            FILTERED.to_string()
+        } else {
+            matched.to_string()
+        }
+    })
+}
+
+/// Checks the length and Luhn checksum of a credit card number.
+fn is_credit_card(number: &str) -> bool {
+    let digits: Vec<u32> = number.chars().filter_map(|c| c.to_digit(10)).collect();
+    if digits.len() < 13 || digits.len() > 19 {
+        return false;
+    }
+    let checksum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(idx, &digit)| match (idx % 2, digit * 2) {
+            (0, _) => digit,
+            (_, doubled) if doubled > 9 => doubled - 9,
+            (_, doubled) => doubled,
+        })
+        .sum::<u32>()
+        % 10;
+    checksum == 0
+}
+
+/// Checks the length and mod-97 checksum of an IBAN.
+fn is_iban(iban: &str) -> bool {
+    let compact: Vec<char> = iban.chars().filter(|c| !c.is_whitespace()).collect();
+    if compact.len() < 15 || compact.len() > 34 {
+        return false;
+    }
+    let rearranged = compact[4..].iter().chain(compact[..4].iter());
+    let mut remainder = 0u32;
+    for c in rearranged {
+        let value = match c.to_digit(36) {
+            Some(value) => value,
+            None => return false,
+        };
+        remainder = if value < 10 {
+            (remainder * 10 + value) % 97
+        } else {
+            (remainder * 100 + value) % 97
+        };
+    }
+    remainder == 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scrubber() -> Scrubber {
+        Scrubber {
+            denylist: vec!["password".into(), "api_key".into()],
+            credit_cards: true,
+            ibans: true,
+        }
+    }
+
+    #[test]
+    fn test_is_denied() {
+        let scrubber = scrubber();
+        assert!(scrubber.is_denied("password"));
+        assert!(scrubber.is_denied("DB_PASSWORD"));
+        assert!(scrubber.is_denied("X-Api-Key"));
+        assert!(!scrubber.is_denied("username"));
+    }
+
+    #[test]
+    fn test_patterns() {
+        let scrubber = scrubber();
+        let mut value = "card 4111 1111 1111 1111, order 1234567890123".to_string();
+        scrubber.scrub_str(&mut value);
+        assert_eq!(value, "card [Filtered], order 1234567890123");
+
+        let mut value = "iban DE89 3704 0044 0532 0130 00 or GB82WEST12345698765432".to_string();
+        scrubber.scrub_str(&mut value);
+        assert_eq!(value, "iban [Filtered] or [Filtered]");
+
+        let mut value = "not an iban: DE00 3704 0044 0532 0130 00".to_string();
+        scrubber.scrub_str(&mut value);
+        assert_eq!(value, "not an iban: DE00 3704 0044 0532 0130 00");
+    }
+
+    #[test]
+    fn test_pairs() {
+        let scrubber = scrubber();
+        let mut query = "user=foo&password=hunter2&api_key=abc".to_string();
+        scrubber.scrub_pairs(&mut query, '&');
+        assert_eq!(query, "user=foo&password=[Filtered]&api_key=[Filtered]");
+    }
+}
@@ -84,12 +84,28 @@ impl Dsn {
 
     /// Returns the submission API URL.
     pub fn store_api_url(&self) -> Url {
+        self.api_url("store")
+    }
+
+    /// Returns the API URL for envelope submission.
+    pub fn envelope_api_url(&self) -> Url {
+        self.api_url("envelope")
+    }
+
+    fn api_url(&self, endpoint: &str) -> Url {
         use std::fmt::Write;
         let mut buf = format!("{}://{}", self.scheme(), self.host());
         if self.port() != self.scheme.default_port() {
             write!(&mut buf, ":{}", self.port()).unwrap();
         }
-        write!(&mut buf, "{}api/{}/store/", self.path, self.project_id()).unwrap();
+        write!(
+            &mut buf,
+            "{}api/{}/{}/",
+            self.path,
+            self.project_id(),
+            endpoint
+        )
+        .unwrap();
         Url::parse(&buf).unwrap()
     }
 
@@ -235,6 +251,10 @@ mod test {
             dsn.store_api_url().to_string(),
             "https://domain/api/42/store/"
         );
+        assert_eq!(
+            dsn.envelope_api_url().to_string(),
+            "https://domain/api/42/envelope/"
+        );
     }
 
     #[test]
@@ -258,6 +278,10 @@ mod test {
             dsn.store_api_url().to_string(),
             "https://domain:8888/api/42/store/"
         );
+        assert_eq!(
+            dsn.envelope_api_url().to_string(),
+            "https://domain:8888/api/42/envelope/"
+        );
     }
 
     #[test]
@@ -10,7 +10,8 @@
 //!
 //! The crate provides a bunch of common types for working with Sentry as
 //! such (DSN, ProjectIDs, authentication headers) as well as types for
-//! the Sentry event protocol.
+//! the Sentry event protocol and the envelope format that is used to
+//! submit events, sessions, transactions and attachments.
 //!
 //! Right now only `v7` of the protocol is implemented but it's versioned
 //! so later versions might be added later.
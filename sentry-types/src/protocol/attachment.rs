@@ -0,0 +1,181 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::str;
+
+use thiserror::Error;
+
+/// The different types an attachment can have.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub enum AttachmentType {
+    /// (default) A standard attachment without special meaning.
+    Attachment,
+    /// A minidump file that creates an error event and is symbolicated. The
+    /// file should start with the `MDMP` magic bytes.
+    Minidump,
+    /// An Apple crash report file that creates an error event and is symbolicated.
+    AppleCrashReport,
+    /// An XML file containing UE4 crash meta data. During event ingestion,
+    /// event contexts and extra fields are extracted from this file.
+    UnrealContext,
+    /// A plain-text log file obtained from UE4 crashes. During event ingestion,
+    /// the last logs are extracted into event breadcrumbs.
+    UnrealLogs,
+}
+
+impl Default for AttachmentType {
+    fn default() -> Self {
+        AttachmentType::Attachment
+    }
+}
+
+impl AttachmentType {
+    /// Gets the string value Sentry expects for the attachment type.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            AttachmentType::Attachment => "event.attachment",
+            AttachmentType::Minidump => "event.minidump",
+            AttachmentType::AppleCrashReport => "event.applecrashreport",
+            AttachmentType::UnrealContext => "unreal.context",
+            AttachmentType::UnrealLogs => "unreal.logs",
+        }
+    }
+}
+
+/// An error used when parsing `AttachmentType`.
+#[derive(Debug, Error)]
+#[error("invalid attachment type")]
+pub struct ParseAttachmentTypeError;
+
+impl str::FromStr for AttachmentType {
+    type Err = ParseAttachmentTypeError;
+
+    fn from_str(s: &str) -> Result<AttachmentType, ParseAttachmentTypeError> {
+        Ok(match s {
+            "event.attachment" => AttachmentType::Attachment,
+            "event.minidump" => AttachmentType::Minidump,
+            "event.applecrashreport" => AttachmentType::AppleCrashReport,
+            "unreal.context" => AttachmentType::UnrealContext,
+            "unreal.logs" => AttachmentType::UnrealLogs,
+            _ => return Err(ParseAttachmentTypeError),
+        })
+    }
+}
+
+impl fmt::Display for AttachmentType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl_str_serde!(AttachmentType);
+
+/// The contents of an attachment.
+#[derive(Clone, PartialEq)]
+pub enum AttachmentData {
+    /// The contents held in memory.
+    Bytes(Vec<u8>),
+    /// The path of a file that is only read when the attachment is sent.
+    Path(PathBuf),
+}
+
+impl Default for AttachmentData {
+    fn default() -> Self {
+        AttachmentData::Bytes(Vec::new())
+    }
+}
+
+impl fmt::Debug for AttachmentData {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            AttachmentData::Bytes(ref bytes) => write!(f, "Bytes({})", bytes.len()),
+            AttachmentData::Path(ref path) => write!(f, "Path({:?})", path),
+        }
+    }
+}
+
+impl From<Vec<u8>> for AttachmentData {
+    fn from(bytes: Vec<u8>) -> Self {
+        AttachmentData::Bytes(bytes)
+    }
+}
+
+impl From<PathBuf> for AttachmentData {
+    fn from(path: PathBuf) -> Self {
+        AttachmentData::Path(path)
+    }
+}
+
+/// Represents an attachment item.
+#[derive(Clone, Default, PartialEq)]
+pub struct Attachment {
+    /// The actual attachment data.
+    pub data: AttachmentData,
+    /// The filename of the attachment.
+    pub filename: String,
+    /// The Content Type of the attachment
+    pub content_type: Option<String>,
+    /// The special type of this attachment.
+    pub ty: Option<AttachmentType>,
+}
+
+impl Attachment {
+    /// Creates an attachment holding the given bytes.
+    pub fn from_bytes<B: Into<Vec<u8>>>(filename: &str, bytes: B) -> Attachment {
+        Attachment {
+            data: AttachmentData::Bytes(bytes.into()),
+            filename: filename.to_string(),
+            ..Default::default()
+        }
+    }
+
+    /// Creates an attachment for the file at the given path.
+    ///
+    /// The file is only read when the attachment is sent.  The filename of
+    /// the attachment is the last component of the path.
+    pub fn from_path<P: AsRef<Path>>(path: P) -> Attachment {
+        let path = path.as_ref();
+        Attachment {
+            filename: path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            data: AttachmentData::Path(path.to_path_buf()),
+            ..Default::default()
+        }
+    }
+
+    /// Returns the contents of the attachment.
+    ///
+    /// For attachments referring to a path the file is read.
+    pub fn bytes(&self) -> io::Result<Vec<u8>> {
+        match self.data {
+            AttachmentData::Bytes(ref bytes) => Ok(bytes.clone()),
+            AttachmentData::Path(ref path) => fs::read(path),
+        }
+    }
+
+    /// Returns an attachment holding its contents in memory.
+    ///
+    /// For attachments referring to a path the file is read.
+    pub fn load(&self) -> io::Result<Attachment> {
+        Ok(Attachment {
+            data: AttachmentData::Bytes(self.bytes()?),
+            filename: self.filename.clone(),
+            content_type: self.content_type.clone(),
+            ty: self.ty,
+        })
+    }
+}
+
+impl fmt::Debug for Attachment {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Attachment")
+            .field("data", &self.data)
+            .field("filename", &self.filename)
+            .field("content_type", &self.content_type)
+            .field("type", &self.ty)
+            .finish()
+    }
+}
@@ -0,0 +1,124 @@
+use std::fmt;
+use std::str;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::utils::ts_seconds_float;
+
+/// The category of data that an envelope item carries.
+///
+/// Sentry uses these categories for rate limiting and to account for
+/// discarded data.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum DataCategory {
+    /// Reserved for data that does not fall into any other category.
+    Default,
+    /// Error events.
+    Error,
+    /// Transaction events.
+    Transaction,
+    /// Release health sessions.
+    Session,
+    /// Attachments.
+    Attachment,
+    /// Internal SDK data such as client reports.
+    Internal,
+}
+
+impl DataCategory {
+    /// Returns the name of the category on sentry.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            DataCategory::Default => "default",
+            DataCategory::Error => "error",
+            DataCategory::Transaction => "transaction",
+            DataCategory::Session => "session",
+            DataCategory::Attachment => "attachment",
+            DataCategory::Internal => "internal",
+        }
+    }
+}
+
+/// An error used when parsing `DataCategory`.
+#[derive(Debug, Error)]
+#[error("unknown data category")]
+pub struct ParseDataCategoryError;
+
+impl str::FromStr for DataCategory {
+    type Err = ParseDataCategoryError;
+
+    fn from_str(s: &str) -> Result<DataCategory, ParseDataCategoryError> {
+        Ok(match s {
+            "default" => DataCategory::Default,
+            "error" => DataCategory::Error,
+            "transaction" => DataCategory::Transaction,
+            "session" => DataCategory::Session,
+            "attachment" => DataCategory::Attachment,
+            "internal" => DataCategory::Internal,
+            _ => return Err(ParseDataCategoryError),
+        })
+    }
+}
+
+impl fmt::Display for DataCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
+impl_str_serde!(DataCategory);
+
+/// The reason why an SDK discarded data instead of sending it.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum DiscardReason {
+    /// The transport queue was full.
+    QueueOverflow,
+    /// The offline cache was full.
+    CacheOverflow,
+    /// The data was dropped because of an active rate limit.
+    RatelimitBackoff,
+    /// The data could not be sent because of a network error.
+    NetworkError,
+    /// The data was dropped by sampling.
+    SampleRate,
+    /// The `before_send` callback dropped the data.
+    BeforeSend,
+    /// An event processor or integration dropped the data.
+    EventProcessor,
+    /// The data matched `ignore_errors` or `ignore_transactions`.
+    Filtered,
+}
+
+/// A single counter of discarded data within a `ClientReport`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct DiscardedEvent {
+    /// Why the data was discarded.
+    pub reason: DiscardReason,
+    /// The category of the discarded data.
+    pub category: DataCategory,
+    /// How many items were discarded.
+    pub quantity: u64,
+}
+
+/// An outcome report of data the SDK discarded.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct ClientReport {
+    /// The time the report was created.
+    #[serde(default = "Utc::now", with = "ts_seconds_float")]
+    pub timestamp: DateTime<Utc>,
+    /// The counters of discarded data.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub discarded_events: Vec<DiscardedEvent>,
+}
+
+impl Default for ClientReport {
+    fn default() -> Self {
+        ClientReport {
+            timestamp: Utc::now(),
+            discarded_events: Default::default(),
+        }
+    }
+}
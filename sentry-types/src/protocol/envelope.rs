@@ -0,0 +1,672 @@
+use std::io::{self, Write};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::dsn::Dsn;
+use crate::protocol::v7::{
+    Attachment, AttachmentData, AttachmentType, ClientReport, DataCategory, Event,
+    SessionAggregates, SessionUpdate, Transaction, UserReport,
+};
+
+/// Represents an envelope parsing error.
+#[derive(Debug, Error)]
+pub enum EnvelopeError {
+    /// Raised if the envelope ends in the middle of an item.
+    #[error("unexpected end of envelope")]
+    UnexpectedEof,
+    /// Raised if a header or payload is not terminated by a newline.
+    #[error("missing newline after header or payload")]
+    MissingNewline,
+    /// Raised if the envelope headers cannot be parsed.
+    #[error("invalid envelope header")]
+    InvalidHeader(#[source] serde_json::Error),
+    /// Raised if the headers of an item cannot be parsed.
+    #[error("invalid item header")]
+    InvalidItemHeader(#[source] serde_json::Error),
+    /// Raised if the payload of an item cannot be parsed.
+    #[error("invalid item payload")]
+    InvalidItemPayload(#[source] serde_json::Error),
+}
+
+/// The headers of an `Envelope`.
+#[derive(Serialize, Deserialize, Debug, Default, Clone, PartialEq)]
+pub struct EnvelopeHeaders {
+    /// The ID of the event or transaction contained in the envelope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event_id: Option<Uuid>,
+    /// The DSN the envelope is meant for.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub dsn: Option<Dsn>,
+    /// The time the envelope was sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sent_at: Option<DateTime<Utc>>,
+    /// The rate the `sampler` chose for the event contained in the envelope.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sample_rate: Option<f32>,
+}
+
+/// The headers of a single item in the envelope wire format.
+#[derive(Serialize, Deserialize, Debug)]
+struct ItemHeaders {
+    #[serde(rename = "type")]
+    ty: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    length: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    filename: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    content_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    attachment_type: Option<AttachmentType>,
+}
+
+/// An item contained in an `Envelope`.
+///
+/// Each item carries one typed payload.  Items of types unknown to this
+/// library are skipped when parsing an envelope.
+#[derive(Clone, Debug, PartialEq)]
+#[allow(clippy::large_enum_variant)]
+pub enum EnvelopeItem {
+    /// An error or message event.
+    Event(Event<'static>),
+    /// An update of a release health session.
+    SessionUpdate(SessionUpdate<'static>),
+    /// Aggregated counts of release health sessions.
+    SessionAggregates(SessionAggregates<'static>),
+    /// A finished tracing transaction.
+    Transaction(Transaction<'static>),
+    /// A file attached to an event.
+    Attachment(Attachment),
+    /// User feedback for an event.
+    UserReport(UserReport),
+    /// A report of data the client discarded.
+    ClientReport(ClientReport),
+}
+
+impl EnvelopeItem {
+    /// Returns the name of the item type on sentry.
+    pub fn type_name(&self) -> &'static str {
+        match *self {
+            EnvelopeItem::Event(..) => "event",
+            EnvelopeItem::SessionUpdate(..) => "session",
+            EnvelopeItem::SessionAggregates(..) => "sessions",
+            EnvelopeItem::Transaction(..) => "transaction",
+            EnvelopeItem::Attachment(..) => "attachment",
+            EnvelopeItem::UserReport(..) => "user_report",
+            EnvelopeItem::ClientReport(..) => "client_report",
+        }
+    }
+
+    /// Returns the data category the item is accounted for.
+    pub fn data_category(&self) -> DataCategory {
+        match *self {
+            EnvelopeItem::Event(..) => DataCategory::Error,
+            EnvelopeItem::SessionUpdate(..) | EnvelopeItem::SessionAggregates(..) => {
+                DataCategory::Session
+            }
+            EnvelopeItem::Transaction(..) => DataCategory::Transaction,
+            EnvelopeItem::Attachment(..) => DataCategory::Attachment,
+            EnvelopeItem::UserReport(..) => DataCategory::Default,
+            EnvelopeItem::ClientReport(..) => DataCategory::Internal,
+        }
+    }
+
+    fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        let mut payload = Vec::new();
+        let mut headers = ItemHeaders {
+            ty: self.type_name().to_string(),
+            length: None,
+            filename: None,
+            content_type: None,
+            attachment_type: None,
+        };
+
+        match *self {
+            EnvelopeItem::Event(ref event) => serde_json::to_writer(&mut payload, event)?,
+            EnvelopeItem::SessionUpdate(ref session) => {
+                serde_json::to_writer(&mut payload, session)?
+            }
+            EnvelopeItem::SessionAggregates(ref aggregates) => {
+                serde_json::to_writer(&mut payload, aggregates)?
+            }
+            EnvelopeItem::Transaction(ref transaction) => {
+                serde_json::to_writer(&mut payload, transaction)?
+            }
+            EnvelopeItem::Attachment(ref attachment) => {
+                headers.filename = Some(attachment.filename.clone());
+                headers.content_type = attachment.content_type.clone();
+                headers.attachment_type = attachment.ty;
+                match attachment.data {
+                    AttachmentData::Bytes(ref bytes) => payload.extend_from_slice(bytes),
+                    AttachmentData::Path(ref path) => payload = std::fs::read(path)?,
+                }
+            }
+            EnvelopeItem::UserReport(ref report) => serde_json::to_writer(&mut payload, report)?,
+            EnvelopeItem::ClientReport(ref report) => serde_json::to_writer(&mut payload, report)?,
+        }
+
+        headers.length = Some(payload.len());
+        serde_json::to_writer(&mut writer, &headers)?;
+        writer.write_all(b"\n")?;
+        writer.write_all(&payload)?;
+        writer.write_all(b"\n")
+    }
+
+    fn from_parts(
+        headers: ItemHeaders,
+        payload: &[u8],
+    ) -> Result<Option<EnvelopeItem>, EnvelopeError> {
+        Ok(Some(match headers.ty.as_str() {
+            "event" => EnvelopeItem::Event(parse_payload(payload)?),
+            "session" => EnvelopeItem::SessionUpdate(parse_payload(payload)?),
+            "sessions" => EnvelopeItem::SessionAggregates(parse_payload(payload)?),
+            "transaction" => EnvelopeItem::Transaction(parse_payload(payload)?),
+            "attachment" => EnvelopeItem::Attachment(Attachment {
+                data: AttachmentData::Bytes(payload.to_vec()),
+                filename: headers.filename.unwrap_or_default(),
+                content_type: headers.content_type,
+                ty: headers.attachment_type,
+            }),
+            "user_report" => EnvelopeItem::UserReport(parse_payload(payload)?),
+            "client_report" => EnvelopeItem::ClientReport(parse_payload(payload)?),
+            _ => return Ok(None),
+        }))
+    }
+}
+
+fn parse_payload<'de, T: Deserialize<'de>>(payload: &'de [u8]) -> Result<T, EnvelopeError> {
+    serde_json::from_slice(payload).map_err(EnvelopeError::InvalidItemPayload)
+}
+
+macro_rules! into_envelope_item {
+    ($kind:ident, $ty:ty) => {
+        impl From<$ty> for EnvelopeItem {
+            fn from(data: $ty) -> Self {
+                EnvelopeItem::$kind(data)
+            }
+        }
+    };
+}
+
+into_envelope_item!(Event, Event<'static>);
+into_envelope_item!(SessionUpdate, SessionUpdate<'static>);
+into_envelope_item!(SessionAggregates, SessionAggregates<'static>);
+into_envelope_item!(Transaction, Transaction<'static>);
+into_envelope_item!(Attachment, Attachment);
+into_envelope_item!(UserReport, UserReport);
+into_envelope_item!(ClientReport, ClientReport);
+
+/// A Sentry envelope.
+///
+/// An envelope is the data format used to send any kind of payload to
+/// Sentry.  It consists of headers and any number of items, each of which
+/// carries its own typed payload.  On the wire an envelope is a newline
+/// delimited sequence of JSON headers, each item's headers being followed
+/// by its payload.
+///
+/// See the [documentation](https://develop.sentry.dev/sdk/envelopes/) for
+/// more details.
+#[derive(Clone, Default, Debug, PartialEq)]
+pub struct Envelope {
+    headers: EnvelopeHeaders,
+    items: Vec<EnvelopeItem>,
+}
+
+impl Envelope {
+    /// Creates a new empty envelope.
+    pub fn new() -> Envelope {
+        Default::default()
+    }
+
+    /// Adds a new item to the envelope.
+    ///
+    /// The ID of an event or transaction is also recorded in the envelope
+    /// headers if none has been set so far.
+    pub fn add_item<I>(&mut self, item: I)
+    where
+        I: Into<EnvelopeItem>,
+    {
+        let item = item.into();
+        if self.headers.event_id.is_none() {
+            match item {
+                EnvelopeItem::Event(ref event) => self.headers.event_id = Some(event.event_id),
+                EnvelopeItem::Transaction(ref transaction) => {
+                    self.headers.event_id = Some(transaction.event_id)
+                }
+                _ => {}
+            }
+        }
+        self.items.push(item);
+    }
+
+    /// Returns the headers of the envelope.
+    pub fn headers(&self) -> &EnvelopeHeaders {
+        &self.headers
+    }
+
+    /// Returns a mutable reference to the headers of the envelope.
+    pub fn headers_mut(&mut self) -> &mut EnvelopeHeaders {
+        &mut self.headers
+    }
+
+    /// Returns the ID of the event or transaction in the envelope, if any.
+    pub fn uuid(&self) -> Option<&Uuid> {
+        self.headers.event_id.as_ref()
+    }
+
+    /// Returns an iterator over the items of the envelope.
+    pub fn items(&self) -> impl Iterator<Item = &EnvelopeItem> {
+        self.items.iter()
+    }
+
+    /// Returns `true` if the envelope does not contain any items.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Returns the event contained in the envelope, if any.
+    pub fn event(&self) -> Option<&Event<'static>> {
+        self.items.iter().find_map(|item| match *item {
+            EnvelopeItem::Event(ref event) => Some(event),
+            _ => None,
+        })
+    }
+
+    /// Consumes the envelope and returns its items.
+    pub fn into_items(self) -> Vec<EnvelopeItem> {
+        self.items
+    }
+
+    /// Retains only the items for which the predicate returns `true`.
+    ///
+    /// Attachments are dropped together with the event or transaction they
+    /// belong to.  If no items remain `None` is returned.
+    pub fn filter<P>(self, mut predicate: P) -> Option<Envelope>
+    where
+        P: FnMut(&EnvelopeItem) -> bool,
+    {
+        let mut filtered = Envelope {
+            headers: self.headers,
+            items: Vec::with_capacity(self.items.len()),
+        };
+        let mut dropped_parent = false;
+        for item in self.items {
+            if predicate(&item) {
+                filtered.items.push(item);
+            } else if let EnvelopeItem::Event(..) | EnvelopeItem::Transaction(..) = item {
+                dropped_parent = true;
+            }
+        }
+        if dropped_parent {
+            filtered.headers.event_id = None;
+            filtered.headers.sample_rate = None;
+            // `matches!` is not available on all supported compilers
+            #[allow(clippy::match_like_matches_macro)]
+            filtered.items.retain(|item| match item {
+                EnvelopeItem::Attachment(..) => false,
+                _ => true,
+            });
+        }
+
+        if filtered.is_empty() {
+            None
+        } else {
+            Some(filtered)
+        }
+    }
+
+    /// Serializes the envelope into the given writer.
+    pub fn to_writer<W: Write>(&self, mut writer: W) -> io::Result<()> {
+        serde_json::to_writer(&mut writer, &self.headers)?;
+        writer.write_all(b"\n")?;
+        for item in &self.items {
+            item.to_writer(&mut writer)?;
+        }
+        Ok(())
+    }
+
+    /// Serializes the envelope into a new buffer.
+    pub fn to_vec(&self) -> Vec<u8> {
+        let mut buf = Vec::new();
+        // writing into a `Vec` and serializing protocol types cannot fail
+        self.to_writer(&mut buf).unwrap();
+        buf
+    }
+
+    /// Parses an envelope from its wire format.
+    ///
+    /// Items without an explicit `length` header are terminated by the next
+    /// newline.  Items of unknown types are skipped.
+    pub fn from_slice(slice: &[u8]) -> Result<Envelope, EnvelopeError> {
+        let (header_line, mut offset) = split_line(slice, 0);
+        let headers = serde_json::from_slice(header_line).map_err(EnvelopeError::InvalidHeader)?;
+        let mut items = Vec::new();
+
+        while offset < slice.len() {
+            if slice[offset] == b'\n' {
+                // tolerate empty lines between items
+                offset += 1;
+                continue;
+            }
+
+            let header_end = match slice[offset..].iter().position(|&b| b == b'\n') {
+                Some(pos) => offset + pos,
+                None => return Err(EnvelopeError::MissingNewline),
+            };
+            let item_headers: ItemHeaders = serde_json::from_slice(&slice[offset..header_end])
+                .map_err(EnvelopeError::InvalidItemHeader)?;
+
+            let payload_start = header_end + 1;
+            let (payload, next) = match item_headers.length {
+                Some(length) => {
+                    let payload_end = payload_start + length;
+                    if payload_end > slice.len() {
+                        return Err(EnvelopeError::UnexpectedEof);
+                    }
+                    match slice.get(payload_end) {
+                        None => (&slice[payload_start..], payload_end),
+                        Some(b'\n') => (&slice[payload_start..payload_end], payload_end + 1),
+                        Some(_) => return Err(EnvelopeError::MissingNewline),
+                    }
+                }
+                None => split_line(slice, payload_start),
+            };
+
+            if let Some(item) = EnvelopeItem::from_parts(item_headers, payload)? {
+                items.push(item);
+            }
+            offset = next;
+        }
+
+        Ok(Envelope { headers, items })
+    }
+}
+
+/// Splits off the line starting at `offset`, returning the line without its
+/// terminating newline and the offset of the following line.
+fn split_line(slice: &[u8], offset: usize) -> (&[u8], usize) {
+    let rest = slice.get(offset..).unwrap_or_default();
+    match rest.iter().position(|&b| b == b'\n') {
+        Some(pos) => (&rest[..pos], offset + pos + 1),
+        None => (rest, slice.len()),
+    }
+}
+
+impl<T> From<T> for Envelope
+where
+    T: Into<EnvelopeItem>,
+{
+    fn from(item: T) -> Self {
+        let mut envelope = Self::default();
+        envelope.add_item(item.into());
+        envelope
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    use chrono::TimeZone;
+
+    use crate::protocol::v7::{Level, SessionAggregateItem, SessionAttributes, SessionStatus};
+
+    fn to_str(envelope: &Envelope) -> String {
+        String::from_utf8(envelope.to_vec()).unwrap()
+    }
+
+    fn event_id() -> Uuid {
+        "22d00b3f-d1b1-4b5d-8d20-49d138cd8a9c".parse().unwrap()
+    }
+
+    fn timestamp() -> DateTime<Utc> {
+        Utc.timestamp_opt(1_595_256_674, 0).unwrap()
+    }
+
+    #[test]
+    fn test_empty() {
+        assert_eq!(to_str(&Envelope::new()), "{}\n");
+    }
+
+    #[test]
+    fn test_event() {
+        let envelope: Envelope = Event {
+            event_id: event_id(),
+            timestamp: timestamp(),
+            ..Default::default()
+        }
+        .into();
+
+        assert_eq!(envelope.uuid(), Some(&event_id()));
+        assert_eq!(
+            to_str(&envelope),
+            "{\"event_id\":\"22d00b3f-d1b1-4b5d-8d20-49d138cd8a9c\"}\n\
+             {\"type\":\"event\",\"length\":70}\n\
+             {\"event_id\":\"22d00b3fd1b14b5d8d2049d138cd8a9c\",\"timestamp\":1595256674}\n"
+        );
+    }
+
+    #[test]
+    fn test_session() {
+        let envelope: Envelope = SessionUpdate {
+            session_id: event_id(),
+            distinct_id: Some("foo@bar.baz".to_owned()),
+            sequence: None,
+            timestamp: None,
+            started: timestamp(),
+            init: true,
+            duration: Some(1.234),
+            status: SessionStatus::Ok,
+            errors: 123,
+            attributes: SessionAttributes {
+                release: "foo-bar@1.2.3".into(),
+                environment: Some("production".into()),
+                ip_address: None,
+                user_agent: None,
+            },
+        }
+        .into();
+
+        assert_eq!(envelope.uuid(), None);
+        assert_eq!(
+            to_str(&envelope),
+            "{}\n\
+             {\"type\":\"session\",\"length\":218}\n\
+             {\"sid\":\"22d00b3f-d1b1-4b5d-8d20-49d138cd8a9c\",\"did\":\"foo@bar.baz\",\
+             \"started\":\"2020-07-20T14:51:14Z\",\"init\":true,\"duration\":1.234,\
+             \"status\":\"ok\",\"errors\":123,\"attrs\":{\"release\":\"foo-bar@1.2.3\",\
+             \"environment\":\"production\"}}\n"
+        );
+    }
+
+    #[test]
+    fn test_session_aggregates() {
+        let envelope: Envelope = SessionAggregates {
+            aggregates: vec![SessionAggregateItem {
+                started: timestamp(),
+                distinct_id: None,
+                exited: 2,
+                errored: 1,
+                abnormal: 0,
+                crashed: 0,
+            }],
+            attributes: SessionAttributes {
+                release: "foo-bar@1.2.3".into(),
+                environment: None,
+                ip_address: None,
+                user_agent: None,
+            },
+        }
+        .into();
+
+        let serialized = to_str(&envelope);
+        assert_eq!(
+            serialized,
+            "{}\n\
+             {\"type\":\"sessions\",\"length\":110}\n\
+             {\"aggregates\":[{\"started\":\"2020-07-20T14:51:14Z\",\"exited\":2,\"errored\":1}],\
+             \"attrs\":{\"release\":\"foo-bar@1.2.3\"}}\n"
+        );
+        assert_eq!(
+            Envelope::from_slice(serialized.as_bytes()).unwrap(),
+            envelope
+        );
+    }
+
+    #[test]
+    fn test_attachment() {
+        let mut envelope = Envelope::new();
+        envelope.add_item(Attachment {
+            data: b"some content".to_vec().into(),
+            filename: "file.txt".into(),
+            content_type: Some("text/plain".into()),
+            ty: Some(AttachmentType::Attachment),
+        });
+
+        assert_eq!(
+            to_str(&envelope),
+            "{}\n\
+             {\"type\":\"attachment\",\"length\":12,\"filename\":\"file.txt\",\
+             \"content_type\":\"text/plain\",\"attachment_type\":\"event.attachment\"}\n\
+             some content\n"
+        );
+    }
+
+    #[test]
+    fn test_attachment_from_path() {
+        let path = std::env::temp_dir().join("sentry-types-attachment-test.log");
+        std::fs::write(&path, "log line\n").unwrap();
+        let attachment = Attachment::from_path(&path);
+        assert_eq!(attachment.filename, "sentry-types-attachment-test.log");
+
+        let envelope: Envelope = attachment.into();
+        let serialized = to_str(&envelope);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(
+            serialized,
+            "{}\n\
+             {\"type\":\"attachment\",\"length\":9,\
+             \"filename\":\"sentry-types-attachment-test.log\"}\n\
+             log line\n\n"
+        );
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let mut envelope = Envelope::new();
+        envelope.headers_mut().dsn = Some("https://public@example.com/1".parse().unwrap());
+        envelope.headers_mut().sample_rate = Some(0.5);
+        envelope.add_item(Event {
+            event_id: event_id(),
+            timestamp: timestamp(),
+            message: Some("multi\nline".into()),
+            level: Level::Warning,
+            ..Default::default()
+        });
+        envelope.add_item(Attachment {
+            data: b"binary\n\0data\n".to_vec().into(),
+            filename: "blob.bin".into(),
+            content_type: None,
+            ty: None,
+        });
+        envelope.add_item(UserReport {
+            event_id: event_id(),
+            name: "Jane".into(),
+            email: "jane@example.com".into(),
+            comments: "It broke".into(),
+        });
+        envelope.add_item(ClientReport {
+            timestamp: timestamp(),
+            discarded_events: vec![],
+        });
+
+        let parsed = Envelope::from_slice(&envelope.to_vec()).unwrap();
+        assert_eq!(parsed, envelope);
+        assert_eq!(
+            parsed.event().unwrap().message.as_deref(),
+            Some("multi\nline")
+        );
+    }
+
+    #[test]
+    fn test_parse_implicit_length() {
+        let envelope = Envelope::from_slice(
+            b"{\"event_id\":\"22d00b3f-d1b1-4b5d-8d20-49d138cd8a9c\"}\n\
+              {\"type\":\"event\"}\n\
+              {\"event_id\":\"22d00b3fd1b14b5d8d2049d138cd8a9c\",\"timestamp\":1595256674}\n\
+              {\"type\":\"attachment\",\"length\":3,\"filename\":\"a.txt\"}\n\
+              abc",
+        )
+        .unwrap();
+
+        let items: Vec<_> = envelope.items().collect();
+        assert_eq!(items.len(), 2);
+        assert_eq!(envelope.event().unwrap().event_id, event_id());
+        match items[1] {
+            EnvelopeItem::Attachment(ref attachment) => {
+                assert_eq!(attachment.filename, "a.txt");
+                assert_eq!(attachment.data, b"abc".to_vec().into());
+            }
+            ref other => panic!("unexpected item {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_parse_skips_unknown_items() {
+        let envelope =
+            Envelope::from_slice(b"{}\n{\"type\":\"unknown\",\"length\":2}\nxx\n").unwrap();
+        assert!(envelope.is_empty());
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        match Envelope::from_slice(b"garbage\n").unwrap_err() {
+            EnvelopeError::InvalidHeader(_) => {}
+            err => panic!("unexpected error: {}", err),
+        }
+        match Envelope::from_slice(b"{}\n{\"type\":\"attachment\",\"length\":10}\nabc").unwrap_err()
+        {
+            EnvelopeError::UnexpectedEof => {}
+            err => panic!("unexpected error: {}", err),
+        }
+        match Envelope::from_slice(b"{}\n{\"type\":\"event\",\"length\":2}\n{}garbage").unwrap_err()
+        {
+            EnvelopeError::MissingNewline => {}
+            err => panic!("unexpected error: {}", err),
+        }
+        match Envelope::from_slice(b"{}\n{\"type\":\"event\"}\nnot json\n").unwrap_err() {
+            EnvelopeError::InvalidItemPayload(_) => {}
+            err => panic!("unexpected error: {}", err),
+        }
+    }
+
+    #[test]
+    fn test_filter() {
+        let mut envelope = Envelope::new();
+        envelope.add_item(Event {
+            event_id: event_id(),
+            ..Default::default()
+        });
+        envelope.add_item(Attachment {
+            data: b"abc".to_vec().into(),
+            filename: "a.txt".into(),
+            content_type: None,
+            ty: None,
+        });
+        envelope.add_item(ClientReport::default());
+
+        let filtered = envelope
+            .clone()
+            .filter(|item| item.type_name() != "event")
+            .unwrap();
+        assert_eq!(filtered.uuid(), None);
+        let items: Vec<_> = filtered.items().collect();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].type_name(), "client_report");
+
+        assert!(envelope.filter(|_| false).is_none());
+    }
+}
@@ -1,5 +1,13 @@
 //! This module exposes the types for the Sentry protocol in different versions.
 
+#[cfg(feature = "with_protocol")]
+mod attachment;
+#[cfg(feature = "with_protocol")]
+mod client_report;
+#[cfg(feature = "with_protocol")]
+mod envelope;
+#[cfg(feature = "with_protocol")]
+mod session;
 #[cfg(feature = "with_protocol")]
 pub mod v7;
 
@@ -0,0 +1,205 @@
+use std::borrow::Cow;
+use std::fmt;
+use std::str;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::protocol::v7::IpAddress;
+
+/// The Status of a Release Health Session.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SessionStatus {
+    /// The session is healthy.
+    ///
+    /// This does not necessarily indicate that the session is still active.
+    Ok,
+    /// The session terminated normally.
+    Exited,
+    /// The session resulted in an application crash.
+    Crashed,
+    /// The session had an unexpected abrupt termination (not crashing).
+    Abnormal,
+    /// The session had at least one error captured but was otherwise healthy.
+    Errored,
+}
+
+impl Default for SessionStatus {
+    fn default() -> Self {
+        SessionStatus::Ok
+    }
+}
+
+/// An error used when parsing `SessionStatus`.
+#[derive(Debug, Error)]
+#[error("invalid session status")]
+pub struct ParseSessionStatusError;
+
+impl str::FromStr for SessionStatus {
+    type Err = ParseSessionStatusError;
+
+    fn from_str(s: &str) -> Result<SessionStatus, ParseSessionStatusError> {
+        Ok(match s {
+            "ok" => SessionStatus::Ok,
+            "exited" => SessionStatus::Exited,
+            "crashed" => SessionStatus::Crashed,
+            "abnormal" => SessionStatus::Abnormal,
+            "errored" => SessionStatus::Errored,
+            _ => return Err(ParseSessionStatusError),
+        })
+    }
+}
+
+impl fmt::Display for SessionStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SessionStatus::Ok => write!(f, "ok"),
+            SessionStatus::Exited => write!(f, "exited"),
+            SessionStatus::Crashed => write!(f, "crashed"),
+            SessionStatus::Abnormal => write!(f, "abnormal"),
+            SessionStatus::Errored => write!(f, "errored"),
+        }
+    }
+}
+
+impl_str_serde!(SessionStatus);
+
+/// Additional attributes for Sessions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionAttributes<'a> {
+    /// The release version string.
+    pub release: Cow<'a, str>,
+    /// The environment identifier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Cow<'a, str>>,
+    /// The ip address of the user. This data is not persisted but used for filtering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ip_address: Option<IpAddress>,
+    /// The user agent of the user. This data is not persisted but used for filtering.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_agent: Option<String>,
+}
+
+impl<'a> SessionAttributes<'a> {
+    /// Creates a fully owned version of the session attributes.
+    pub fn into_owned(self) -> SessionAttributes<'static> {
+        SessionAttributes {
+            release: Cow::Owned(self.release.into_owned()),
+            environment: self.environment.map(|x| Cow::Owned(x.into_owned())),
+            ip_address: self.ip_address,
+            user_agent: self.user_agent,
+        }
+    }
+}
+
+fn is_false(val: &bool) -> bool {
+    !val
+}
+
+fn is_zero(val: &u32) -> bool {
+    *val == 0
+}
+
+/// A Release Health Session.
+///
+/// Refer to the [Sessions](https://develop.sentry.dev/sdk/sessions/) documentation
+/// for more details.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionUpdate<'a> {
+    /// The session identifier.
+    #[serde(rename = "sid", default = "Uuid::new_v4")]
+    pub session_id: Uuid,
+    /// The distinct identifier. Should be device or user ID.
+    #[serde(rename = "did", default, skip_serializing_if = "Option::is_none")]
+    pub distinct_id: Option<String>,
+    /// An optional logical clock.
+    #[serde(rename = "seq", default, skip_serializing_if = "Option::is_none")]
+    pub sequence: Option<u64>,
+    /// The timestamp of when the session change event was created.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// The timestamp of when the session itself started.
+    #[serde(default = "Utc::now")]
+    pub started: DateTime<Utc>,
+    /// A flag that indicates that this is the initial transmission of the session.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub init: bool,
+    /// An optional duration of the session so far.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duration: Option<f64>,
+    /// The status of the session.
+    #[serde(default)]
+    pub status: SessionStatus,
+    /// The number of errors that ocurred.
+    #[serde(default)]
+    pub errors: u64,
+    /// The session event attributes.
+    #[serde(rename = "attrs")]
+    pub attributes: SessionAttributes<'a>,
+}
+
+impl<'a> SessionUpdate<'a> {
+    /// Creates a fully owned version of the session update.
+    pub fn into_owned(self) -> SessionUpdate<'static> {
+        SessionUpdate {
+            session_id: self.session_id,
+            distinct_id: self.distinct_id,
+            sequence: self.sequence,
+            timestamp: self.timestamp,
+            started: self.started,
+            init: self.init,
+            duration: self.duration,
+            status: self.status,
+            errors: self.errors,
+            attributes: self.attributes.into_owned(),
+        }
+    }
+}
+
+/// An aggregation of sessions grouped by `started` and `distinct_id`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionAggregateItem {
+    /// The timestamp of when the sessions started, rounded to the minute.
+    pub started: DateTime<Utc>,
+    /// The distinct identifier.
+    #[serde(rename = "did", default, skip_serializing_if = "Option::is_none")]
+    pub distinct_id: Option<String>,
+    /// The number of sessions that terminated normally.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub exited: u32,
+    /// The number of sessions that had at least one error.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub errored: u32,
+    /// The number of sessions that terminated abnormally.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub abnormal: u32,
+    /// The number of sessions that crashed.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub crashed: u32,
+}
+
+/// Aggregated Release Health Sessions.
+///
+/// Servers create one session per request, these are sent in aggregated
+/// form instead of as individual updates.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct SessionAggregates<'a> {
+    /// The session counts per bucket.
+    #[serde(default)]
+    pub aggregates: Vec<SessionAggregateItem>,
+    /// The attributes shared by all aggregated sessions.
+    #[serde(rename = "attrs")]
+    pub attributes: SessionAttributes<'a>,
+}
+
+impl<'a> SessionAggregates<'a> {
+    /// Creates a fully owned version of the session aggregates.
+    pub fn into_owned(self) -> SessionAggregates<'static> {
+        SessionAggregates {
+            aggregates: self.aggregates,
+            attributes: self.attributes.into_owned(),
+        }
+    }
+}
@@ -22,7 +22,12 @@ use thiserror::Error;
 use url::Url;
 use uuid::Uuid;
 
-use crate::utils::ts_seconds_float;
+use crate::utils::{ts_seconds_float, ts_seconds_float_opt};
+
+pub use super::attachment::*;
+pub use super::client_report::*;
+pub use super::envelope::*;
+pub use super::session::*;
 
 /// An arbitrary (JSON) value.
 pub mod value {
@@ -1046,6 +1051,8 @@ pub enum Context {
     App(Box<AppContext>),
     /// Web browser data.
     Browser(Box<BrowserContext>),
+    /// Tracing data.
+    Trace(Box<TraceContext>),
     /// Generic other context data.
     #[serde(rename = "unknown")]
     Other(Map<String, Value>),
@@ -1060,6 +1067,7 @@ impl Context {
             Context::Runtime(..) => "runtime",
             Context::App(..) => "app",
             Context::Browser(..) => "browser",
+            Context::Trace(..) => "trace",
             Context::Other(..) => "unknown",
         }
     }
@@ -1214,6 +1222,29 @@ pub struct BrowserContext {
     pub other: Map<String, Value>,
 }
 
+/// Holds information about a tracing event.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TraceContext {
+    /// The ID of the span this context belongs to.
+    #[serde(default)]
+    pub span_id: SpanId,
+    /// The ID of the trace the span belongs to.
+    #[serde(default)]
+    pub trace_id: TraceId,
+    /// The optional ID of the parent span.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_span_id: Option<SpanId>,
+    /// A short code identifying the type of operation the span is measuring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<String>,
+    /// Human readable detail description.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Describes the status of the span (e.g. `ok`, `cancelled`, etc.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<SpanStatus>,
+}
+
 macro_rules! into_context {
     ($kind:ident, $ty:ty) => {
         impl From<$ty> for Context {
@@ -1229,6 +1260,7 @@ into_context!(Device, DeviceContext);
 into_context!(Os, OsContext);
 into_context!(Runtime, RuntimeContext);
 into_context!(Browser, BrowserContext);
+into_context!(Trace, TraceContext);
 
 mod event {
     use super::*;
@@ -1451,3 +1483,333 @@ impl<'a> fmt::Display for Event<'a> {
         write!(f, "Event(id: {}, ts: {})", self.event_id, self.timestamp)
     }
 }
+
+/// Raised if a trace or span ID cannot be parsed.
+#[derive(Debug, Error)]
+#[error("invalid id")]
+pub struct ParseIdError;
+
+macro_rules! impl_hex_id {
+    ($type:ident, $len:expr) => {
+        impl $type {
+            /// Creates an ID from its raw bytes.
+            pub fn from_bytes(bytes: [u8; $len]) -> $type {
+                $type(bytes)
+            }
+
+            /// Returns the raw bytes of the ID.
+            pub fn as_bytes(&self) -> &[u8; $len] {
+                &self.0
+            }
+        }
+
+        impl Default for $type {
+            fn default() -> $type {
+                let mut bytes = [0; $len];
+                bytes.copy_from_slice(&Uuid::new_v4().as_bytes()[..$len]);
+                $type(bytes)
+            }
+        }
+
+        impl fmt::Display for $type {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                for byte in &self.0 {
+                    write!(f, "{:02x}", byte)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl fmt::Debug for $type {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "{}({})", stringify!($type), self)
+            }
+        }
+
+        impl str::FromStr for $type {
+            type Err = ParseIdError;
+
+            fn from_str(s: &str) -> Result<$type, ParseIdError> {
+                if s.len() != $len * 2 || !s.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    return Err(ParseIdError);
+                }
+                let mut bytes = [0; $len];
+                for (idx, byte) in bytes.iter_mut().enumerate() {
+                    *byte = u8::from_str_radix(&s[idx * 2..idx * 2 + 2], 16)
+                        .map_err(|_| ParseIdError)?;
+                }
+                Ok($type(bytes))
+            }
+        }
+
+        impl_str_serde!($type);
+    };
+}
+
+/// A 16 byte identifier of a trace.
+///
+/// The default value is a randomly generated ID.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TraceId([u8; 16]);
+
+impl_hex_id!(TraceId, 16);
+
+/// An 8 byte identifier of a span within a trace.
+///
+/// The default value is a randomly generated ID.
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SpanId([u8; 8]);
+
+impl_hex_id!(SpanId, 8);
+
+/// The status of a span.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum SpanStatus {
+    /// The operation completed successfully.
+    Ok,
+    /// Deadline expired before operation could complete.
+    DeadlineExceeded,
+    /// 401 Unauthorized (actually does mean unauthenticated according to RFC 7235)
+    Unauthenticated,
+    /// 403 Forbidden
+    PermissionDenied,
+    /// 404 Not Found. Some requested entity (file or directory) was not found.
+    NotFound,
+    /// 429 Too Many Requests
+    ResourceExhausted,
+    /// Client specified an invalid argument. 4xx.
+    InvalidArgument,
+    /// 501 Not Implemented
+    Unimplemented,
+    /// 503 Service Unavailable
+    Unavailable,
+    /// Other/generic 5xx.
+    InternalError,
+    /// Unknown. Any non-standard HTTP status code.
+    #[serde(rename = "unknown")]
+    UnknownError,
+    /// The operation was cancelled (typically by the user).
+    Cancelled,
+    /// Already exists (409)
+    AlreadyExists,
+    /// The system is not in a state required for the operation's execution.
+    FailedPrecondition,
+    /// The operation was aborted, typically due to a concurrency issue.
+    Aborted,
+    /// Operation was attempted past the valid range.
+    OutOfRange,
+    /// Unrecoverable data loss or corruption
+    DataLoss,
+}
+
+/// Represents a tracing span.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Span {
+    /// The ID of the span
+    #[serde(default)]
+    pub span_id: SpanId,
+    /// Determines which trace the span belongs to.
+    #[serde(default)]
+    pub trace_id: TraceId,
+    /// Determines the parent of the span, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_span_id: Option<SpanId>,
+    /// Determines whether this span is generated in the same process as its parent, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub same_process_as_parent: Option<bool>,
+    /// Short code identifying the type of operation the span is measuring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub op: Option<String>,
+    /// Longer description of the span's operation, which uniquely identifies the span
+    /// but is consistent across instances of the span.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// The timestamp at which the measuring of the span finished.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "ts_seconds_float_opt"
+    )]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// The timestamp at which the measuring of the span started.
+    #[serde(default = "event::default_timestamp", with = "ts_seconds_float")]
+    pub start_timestamp: DateTime<Utc>,
+    /// Describes the status of the span (e.g. `ok`, `cancelled`, etc.)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<SpanStatus>,
+    /// Optional tags to be attached to the span.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub tags: Map<String, String>,
+    /// Optional extra information to be sent with the span.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub data: Map<String, Value>,
+}
+
+impl Default for Span {
+    fn default() -> Self {
+        Span {
+            span_id: Default::default(),
+            trace_id: Default::default(),
+            timestamp: Default::default(),
+            tags: Default::default(),
+            start_timestamp: event::default_timestamp(),
+            description: Default::default(),
+            status: Default::default(),
+            parent_span_id: Default::default(),
+            same_process_as_parent: Default::default(),
+            op: Default::default(),
+            data: Default::default(),
+        }
+    }
+}
+
+impl Span {
+    /// Creates a new span with the current timestamp and random id.
+    pub fn new() -> Span {
+        Default::default()
+    }
+
+    /// Finalizes the span by setting its end timestamp to now.
+    pub fn finish(&mut self) {
+        self.timestamp = Some(Utc::now());
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Span(id: {}, ts: {})",
+            self.span_id, self.start_timestamp
+        )
+    }
+}
+
+/// Represents a tracing transaction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Transaction<'a> {
+    /// The ID of the event
+    #[serde(default = "event::default_id", serialize_with = "event::serialize_id")]
+    pub event_id: Uuid,
+    /// The transaction name.
+    #[serde(
+        rename = "transaction",
+        default,
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub name: Option<String>,
+    /// A release identifier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub release: Option<Cow<'a, str>>,
+    /// An optional environment identifier.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub environment: Option<Cow<'a, str>>,
+    /// Optional tags to be attached to the event.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub tags: Map<String, String>,
+    /// Optional extra information to be sent with the event.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub extra: Map<String, Value>,
+    /// SDK metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sdk: Option<Cow<'a, ClientSdkInfo>>,
+    /// A platform identifier for this event.
+    #[serde(
+        default = "event::default_platform",
+        skip_serializing_if = "event::is_default_platform"
+    )]
+    pub platform: Cow<'a, str>,
+    /// The end time of the transaction.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        with = "ts_seconds_float_opt"
+    )]
+    pub timestamp: Option<DateTime<Utc>>,
+    /// The start time of the transaction.
+    #[serde(default = "event::default_timestamp", with = "ts_seconds_float")]
+    pub start_timestamp: DateTime<Utc>,
+    /// The collection of finished spans part of this transaction.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub spans: Vec<Span>,
+    /// Optional contexts.
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub contexts: Map<String, Context>,
+}
+
+impl<'a> Default for Transaction<'a> {
+    fn default() -> Self {
+        Transaction {
+            event_id: event::default_id(),
+            name: Default::default(),
+            release: Default::default(),
+            environment: Default::default(),
+            tags: Default::default(),
+            extra: Default::default(),
+            sdk: Default::default(),
+            platform: event::default_platform(),
+            timestamp: Default::default(),
+            start_timestamp: event::default_timestamp(),
+            spans: Default::default(),
+            contexts: Default::default(),
+        }
+    }
+}
+
+impl<'a> Transaction<'a> {
+    /// Creates a new transaction with the current timestamp and random id.
+    pub fn new() -> Transaction<'a> {
+        Default::default()
+    }
+
+    /// Creates a fully owned version of the transaction.
+    pub fn into_owned(self) -> Transaction<'static> {
+        Transaction {
+            event_id: self.event_id,
+            name: self.name,
+            release: self.release.map(|x| Cow::Owned(x.into_owned())),
+            environment: self.environment.map(|x| Cow::Owned(x.into_owned())),
+            tags: self.tags,
+            extra: self.extra,
+            sdk: self.sdk.map(|x| Cow::Owned(x.into_owned())),
+            platform: Cow::Owned(self.platform.into_owned()),
+            timestamp: self.timestamp,
+            start_timestamp: self.start_timestamp,
+            spans: self.spans,
+            contexts: self.contexts,
+        }
+    }
+
+    /// Finalizes the transaction by setting its end timestamp to now.
+    pub fn finish(&mut self) {
+        self.timestamp = Some(Utc::now());
+    }
+}
+
+impl<'a> fmt::Display for Transaction<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Transaction(id: {}, ts: {})",
+            self.event_id, self.start_timestamp
+        )
+    }
+}
+
+/// User feedback for an event.
+///
+/// This is sent alongside (or after) an event to collect comments of the
+/// affected user.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct UserReport {
+    /// The ID of the event the feedback is about.
+    #[serde(serialize_with = "event::serialize_id")]
+    pub event_id: Uuid,
+    /// The name of the user.
+    pub name: String,
+    /// The email address of the user.
+    pub email: String,
+    /// The comments of the user.
+    pub comments: String,
+}
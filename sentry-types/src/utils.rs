@@ -89,3 +89,30 @@ pub mod ts_seconds_float {
         }
     }
 }
+
+pub mod ts_seconds_float_opt {
+    use chrono::{DateTime, Utc};
+    use serde::{de, ser, Deserialize};
+
+    use super::ts_seconds_float;
+
+    pub fn deserialize<'de, D>(d: D) -> Result<Option<DateTime<Utc>>, D::Error>
+    where
+        D: de::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        struct Wrapper(#[serde(with = "ts_seconds_float")] DateTime<Utc>);
+
+        Ok(Option::<Wrapper>::deserialize(d)?.map(|wrapper| wrapper.0))
+    }
+
+    pub fn serialize<S>(dt: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: ser::Serializer,
+    {
+        match *dt {
+            Some(ref dt) => ts_seconds_float::serialize(dt, serializer),
+            None => serializer.serialize_none(),
+        }
+    }
+}
@@ -1357,6 +1357,101 @@ mod test_contexts {
     }
 }
 
+mod test_transaction {
+    use super::*;
+
+    fn trace_id() -> v7::TraceId {
+        "4c79f60c11214eb38604f4ae0781bfb2".parse().unwrap()
+    }
+
+    fn span_id() -> v7::SpanId {
+        "fa90fdead5f74052".parse().unwrap()
+    }
+
+    #[test]
+    fn test_ids() {
+        assert_eq!(trace_id().to_string(), "4c79f60c11214eb38604f4ae0781bfb2");
+        assert_eq!(span_id().to_string(), "fa90fdead5f74052");
+        assert_eq!(
+            serde_json::to_string(&span_id()).unwrap(),
+            "\"fa90fdead5f74052\""
+        );
+        assert!("fa90fdead5f7405".parse::<v7::SpanId>().is_err());
+        assert!("fa90fdead5f7405x".parse::<v7::SpanId>().is_err());
+        assert!(v7::TraceId::default() != v7::TraceId::default());
+    }
+
+    #[test]
+    fn test_trace_context() {
+        let event = v7::Event {
+            event_id: event_id(),
+            timestamp: event_time(),
+            contexts: {
+                let mut m = v7::Map::new();
+                m.insert(
+                    "trace".into(),
+                    v7::TraceContext {
+                        span_id: span_id(),
+                        trace_id: trace_id(),
+                        op: Some("http.server".into()),
+                        status: Some(v7::SpanStatus::UnknownError),
+                        ..Default::default()
+                    }
+                    .into(),
+                );
+                m
+            },
+            ..Default::default()
+        };
+
+        assert_roundtrip(&event);
+        assert_eq!(
+            serde_json::to_string(&event).unwrap(),
+            "{\"event_id\":\"d43e86c96e424a93a4fbda156dd17341\",\"timestamp\":1514103120,\
+             \"contexts\":{\"trace\":{\"type\":\"trace\",\"span_id\":\"fa90fdead5f74052\",\
+             \"trace_id\":\"4c79f60c11214eb38604f4ae0781bfb2\",\"op\":\"http.server\",\
+             \"status\":\"unknown\"}}}"
+        );
+    }
+
+    #[test]
+    fn test_transaction() {
+        let transaction = v7::Transaction {
+            event_id: event_id(),
+            name: Some("GET /".into()),
+            start_timestamp: event_time(),
+            timestamp: Some(event_time() + chrono::Duration::milliseconds(1500)),
+            spans: vec![v7::Span {
+                span_id: span_id(),
+                trace_id: trace_id(),
+                parent_span_id: Some("b1e1e4b05a1f4e4a".parse().unwrap()),
+                op: Some("db".into()),
+                description: Some("SELECT * FROM users".into()),
+                start_timestamp: event_time(),
+                timestamp: Some(event_time()),
+                status: Some(v7::SpanStatus::Ok),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let json = serde_json::to_string(&transaction).unwrap();
+        assert_eq!(
+            json,
+            "{\"event_id\":\"d43e86c96e424a93a4fbda156dd17341\",\"transaction\":\"GET /\",\
+             \"timestamp\":1514103121.5,\"start_timestamp\":1514103120,\"spans\":[{\
+             \"span_id\":\"fa90fdead5f74052\",\"trace_id\":\"4c79f60c11214eb38604f4ae0781bfb2\",\
+             \"parent_span_id\":\"b1e1e4b05a1f4e4a\",\"op\":\"db\",\
+             \"description\":\"SELECT * FROM users\",\"timestamp\":1514103120,\
+             \"start_timestamp\":1514103120,\"status\":\"ok\"}]}"
+        );
+        assert_eq!(
+            serde_json::from_str::<v7::Transaction<'_>>(&json).unwrap(),
+            transaction
+        );
+    }
+}
+
 #[test]
 fn test_level_log() {
     assert_eq!(v7::Level::Info, serde_json::from_str("\"log\"").unwrap());
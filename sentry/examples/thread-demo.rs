@@ -1,6 +1,3 @@
-use std::sync::Arc;
-use std::thread;
-
 fn main() {
     let mut log_builder = pretty_env_logger::formatted_builder();
     log_builder.parse_filters("info");
@@ -24,28 +21,22 @@ fn main() {
     // the log integration sends to Hub::current()
     log::info!("Spawning thread");
 
-    thread::spawn(|| {
+    sentry::thread::spawn(|| {
         // The thread spawned here gets a new hub cloned from the hub of the
         // main thread.
         log::info!("Spawned thread, configuring scope.");
 
-        // now we want to create a new hub based on the thread's normal hub for
-        // working with it explicitly.
-        let hub = Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
-
-        // reconfigure that scope.
-        hub.configure_scope(|scope| {
+        // reconfigure the scope of this thread's hub.
+        sentry::configure_scope(|scope| {
             scope.set_tag("worker", "worker1");
         });
 
-        // we can now bind a new thread and have the other thread run some code
-        // bound to the hub we just created.
-        thread::spawn(move || {
-            sentry::Hub::run(hub, || {
-                // the log integration picks up the Hub::current which is now bound
-                // to the outer hub.
-                log::error!("Failing!");
-            });
+        // threads spawned with `sentry::thread::spawn` get a hub based on the
+        // hub of the spawning thread instead of the main thread.
+        sentry::thread::spawn(|| {
+            // the log integration picks up the Hub::current which is based on
+            // the hub of the outer thread, so the event is tagged with `worker`.
+            log::error!("Failing!");
         })
         .join()
         .unwrap();
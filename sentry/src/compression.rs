@@ -0,0 +1,129 @@
+use miniz_oxide::deflate::{compress_to_vec, compress_to_vec_zlib};
+
+use crate::Compression;
+
+/// The header of a gzip member without file name, comment or timestamp.
+///
+/// `miniz_oxide` only produces raw deflate and zlib streams, so the gzip
+/// framing (RFC 1952) is added here instead of pulling in `flate2`, which
+/// none of the HTTP clients depend on.
+const GZIP_HEADER: [u8; 10] = [0x1f, 0x8b, 8, 0, 0, 0, 0, 0, 0, 0xff];
+
+/// Compresses a request body.
+///
+/// Returns the body to send together with the value of the
+/// `Content-Encoding` header, which is `None` for uncompressed bodies.
+pub(crate) fn compress(compression: Compression, body: Vec<u8>) -> (Vec<u8>, Option<&'static str>) {
+    match compression {
+        Compression::None => (body, None),
+        Compression::Gzip(level) => (gzip(&body, level), Some("gzip")),
+        Compression::Deflate(level) => (
+            compress_to_vec_zlib(&body, clamp_level(level)),
+            Some("deflate"),
+        ),
+    }
+}
+
+fn clamp_level(level: u32) -> u8 {
+    level.min(9) as u8
+}
+
+fn gzip(data: &[u8], level: u32) -> Vec<u8> {
+    let deflated = compress_to_vec(data, clamp_level(level));
+    let mut rv = Vec::with_capacity(GZIP_HEADER.len() + deflated.len() + 8);
+    rv.extend_from_slice(&GZIP_HEADER);
+    rv.extend_from_slice(&deflated);
+    rv.extend_from_slice(&crc32(data).to_le_bytes());
+    rv.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    rv
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+    use std::thread;
+
+    use miniz_oxide::inflate::{decompress_to_vec, decompress_to_vec_zlib};
+
+    const BODY: &[u8] = b"{\"message\":\"hello hello hello hello hello\"}";
+
+    /// Decompresses a body with the `gzip` tool, which verifies the framing
+    /// and the checksums.
+    fn gunzip(body: &[u8]) -> Vec<u8> {
+        let mut child = Command::new("gzip")
+            .arg("-dc")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .expect("gzip is required to run this test");
+        let mut stdin = child.stdin.take().unwrap();
+        let body = body.to_vec();
+        // the body is written from another thread so that a full stdout pipe
+        // cannot block the write
+        let writer = thread::spawn(move || stdin.write_all(&body));
+        let output = child.wait_with_output().unwrap();
+        writer.join().unwrap().unwrap();
+        assert!(output.status.success());
+        output.stdout
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b"123456789"), 0xcbf4_3926);
+        assert_eq!(crc32(b""), 0);
+    }
+
+    #[test]
+    fn test_gzip() {
+        let (body, encoding) = compress(Compression::Gzip(6), BODY.to_vec());
+        assert_eq!(encoding, Some("gzip"));
+        assert_eq!(&body[..2], &[0x1f, 0x8b]);
+
+        let trailer = &body[body.len() - 8..];
+        assert_eq!(&trailer[..4], &crc32(BODY).to_le_bytes());
+        assert_eq!(&trailer[4..], &(BODY.len() as u32).to_le_bytes());
+
+        let deflated = &body[GZIP_HEADER.len()..body.len() - 8];
+        assert_eq!(decompress_to_vec(deflated).unwrap(), BODY);
+    }
+
+    #[test]
+    fn test_gzip_roundtrip() {
+        let large: Vec<u8> = (0..200_000u32).map(|i| (i * 7919 % 251) as u8).collect();
+        for data in &[&b""[..], BODY, &large] {
+            for &level in &[0, 1, 6, 9] {
+                let (body, _) = compress(Compression::Gzip(level), data.to_vec());
+                assert_eq!(&gunzip(&body)[..], *data);
+            }
+        }
+    }
+
+    #[test]
+    fn test_deflate_and_none() {
+        let (body, encoding) = compress(Compression::Deflate(12), BODY.to_vec());
+        assert_eq!(encoding, Some("deflate"));
+        assert_eq!(decompress_to_vec_zlib(&body).unwrap(), BODY);
+
+        let (body, encoding) = compress(Compression::None, BODY.to_vec());
+        assert_eq!(encoding, None);
+        assert_eq!(body, BODY);
+    }
+}
@@ -3,7 +3,9 @@ use std::{borrow::Cow, sync::Arc};
 
 use crate::transports::DefaultTransportFactory;
 
+use crate::integrations::DedupeIntegration;
 use crate::internals::Dsn;
+use crate::local_transport::{LocalTarget, LOCAL_TRANSPORT_DSN};
 use crate::{ClientOptions, Integration};
 
 /// Apply default client options.
@@ -13,6 +15,10 @@ use crate::{ClientOptions, Integration};
 /// also sets the `dsn`, `release`, `environment`, and proxy settings based on
 /// environment variables.
 ///
+/// If `SENTRY_TRANSPORT` selects a local transport for the default transport
+/// factory and no DSN is configured, a placeholder DSN is set so that the
+/// client is enabled without a Sentry server.
+///
 /// # Examples
 /// ```
 /// std::env::set_var("SENTRY_RELEASE", "release-from-env");
@@ -26,7 +32,8 @@ use crate::{ClientOptions, Integration};
 /// assert!(options.transport.is_some());
 /// ```
 pub fn apply_defaults(mut opts: ClientOptions) -> ClientOptions {
-    if opts.transport.is_none() {
+    let default_transport = opts.transport.is_none();
+    if default_transport {
         opts.transport = Some(Arc::new(DefaultTransportFactory));
     }
     if opts.default_integrations {
@@ -60,6 +67,7 @@ pub fn apply_defaults(mut opts: ClientOptions) -> ClientOptions {
                 sentry_backtrace::ProcessStacktraceIntegration::default(),
             ));
         }
+        integrations.push(Arc::new(DedupeIntegration::default()));
         integrations.extend(opts.integrations.into_iter());
         opts.integrations = integrations;
     }
@@ -68,6 +76,9 @@ pub fn apply_defaults(mut opts: ClientOptions) -> ClientOptions {
             .ok()
             .and_then(|dsn| dsn.parse::<Dsn>().ok());
     }
+    if opts.dsn.is_none() && default_transport && LocalTarget::from_env().is_some() {
+        opts.dsn = LOCAL_TRANSPORT_DSN.parse().ok();
+    }
     if opts.release.is_none() {
         opts.release = env::var("SENTRY_RELEASE").ok().map(Cow::Owned);
     }
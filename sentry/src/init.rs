@@ -2,11 +2,12 @@ use std::sync::Arc;
 
 use sentry_core::sentry_debug;
 
-use crate::{defaults::apply_defaults, Client, ClientOptions, Hub};
+use crate::{defaults::apply_defaults, Client, ClientOptions, Hub, SessionMode};
 
 /// Helper struct that is returned from `init`.
 ///
-/// When this is dropped events are drained with a 1 second timeout.
+/// When this is dropped events are drained with a 1 second timeout.  If
+/// automatic session tracking is enabled the session is ended first.
 #[must_use = "when the init guard is dropped the transport will be shut down and no further \
               events can be sent.  If you do want to ignore this use mem::forget on it."]
 pub struct ClientInitGuard(Arc<Client>);
@@ -32,10 +33,17 @@ impl Drop for ClientInitGuard {
         } else {
             sentry_debug!("dropping client guard (no client to dispose)");
         }
+        if tracks_application_session(self.0.options()) {
+            Hub::with_active(|hub| hub.end_session());
+        }
         self.0.close(None);
     }
 }
 
+fn tracks_application_session(options: &ClientOptions) -> bool {
+    options.auto_session_tracking && options.session_mode == SessionMode::Application
+}
+
 /// Creates the Sentry client for a given client config and binds it.
 ///
 /// This returns a client init guard that must kept in scope will help the
@@ -93,6 +101,9 @@ where
     Hub::with(|hub| hub.bind_client(Some(client.clone())));
     if let Some(dsn) = client.dsn() {
         sentry_debug!("enabled sentry client for DSN {}", dsn);
+        if tracks_application_session(client.options()) {
+            Hub::with_active(|hub| hub.start_session());
+        }
     } else {
         sentry_debug!("initialized disabled sentry client due to disabled or invalid DSN");
     }
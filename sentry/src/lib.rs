@@ -49,6 +49,11 @@
 //! can be temporarily bound to a thread with `Hub::run`.  For more information see
 //! [`Hub`](struct.Hub.html).
 //!
+//! Because new threads are based on the main hub, data configured on the hub of another thread is
+//! not carried over to the threads it spawns.  The [`thread`](thread/index.html) module provides
+//! replacements for `std::thread::spawn` and `std::thread::Builder` that run the new thread with a
+//! hub based on the current one.  `Hub::bind_to_closure` does the same for any closure.
+//!
 //! Users are expected to reconfigure the scope with [`configure_scope`](fn.configure_scope.html).
 //! For more elaborate scope management the hub needs to be interfaced with directly.
 //!
@@ -57,6 +62,10 @@
 //! of some integrations some functionality like automatic breadcrumb recording depends on the
 //! thread local hub being correctly configured.
 //!
+//! For futures `FutureExt::bind_hub` binds a hub to the execution of a future and
+//! `FutureExt::fork_hub` binds a new hub based on the current one, which gives every task a scope
+//! of its own.
+//!
 //! # Minimal API
 //!
 //! This crate can also be used in "minimal" mode.  This is enabled by disabling all default
@@ -87,25 +96,74 @@
 //! Additional integrations:
 //!
 //! * `with_debug_to_log`: When enabled sentry will debug log to a debug log at all times.
+//! * `with_rayon`: Enables the [`rayon`](rayon/index.html) module to run rayon tasks with a hub
+//!   based on the current one.
+//! * `with_tokio`: Enables the [`tokio`](tokio/index.html) module to spawn tokio tasks with a hub
+//!   of their own.
 //!
 //! Additional transports:
 //! * `with_reqwest_transport`: Enables the reqwest transport explicitly.  This is currently the
 //!   default transport.
 //! * `with_curl_transport`: Enables the curl transport.
+//! * `with_tokio_transport`: Enables the tokio transport, which sends events from a task on
+//!   an existing tokio runtime instead of a thread of its own.
 //! * `with_rustls`: Enables the `rustls` TLS implementation.  This is currently the default when
 //!   using the `with_reqwest_transport` feature.
-//! * `with_native_tls`: Enables the `default-tls` feature of the `reqwest` library.
+//! * `with_native_tls`: Enables the `native-tls` feature of the `reqwest` library, which uses
+//!   the TLS implementation of the platform and supports PKCS #12 client identities.
 //!
 //! Testing:
 //!
 //! * `with_test_support`: Enables the test support module.
 #![warn(missing_docs)]
 
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+mod compression;
 #[cfg(feature = "with_client_implementation")]
 mod defaults;
 #[cfg(feature = "with_client_implementation")]
 mod init;
 #[cfg(feature = "with_client_implementation")]
+mod local_transport;
+#[cfg(feature = "with_client_implementation")]
+mod multiplex;
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+mod queue;
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+mod ratelimit;
+#[cfg(feature = "with_rayon")]
+pub mod rayon;
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+mod spool;
+#[cfg(feature = "with_client_implementation")]
+pub mod thread;
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+mod tls;
+#[cfg(feature = "with_tokio")]
+pub mod tokio;
+#[cfg(feature = "with_tokio_transport")]
+mod tokio_transport;
+#[cfg(feature = "with_client_implementation")]
 mod transport;
 
 #[doc(inline)]
@@ -129,17 +187,37 @@ pub mod internals {
 /// The provided transports.
 ///
 /// This module exposes all transports that are compiled into the sentry
-/// library.  The `with_reqwest_transport` and `with_curl_transport` flags
-/// turn on these transports.
+/// library.  The `with_reqwest_transport`, `with_curl_transport` and
+/// `with_tokio_transport` flags turn on these transports.  The file and
+/// stdout transports are always available with the client implementation.
 pub mod transports {
+    #[cfg(feature = "with_client_implementation")]
+    pub use crate::transport::DefaultTransportFactory;
+
+    #[cfg(feature = "with_client_implementation")]
+    pub use crate::local_transport::{FileFormat, FileTransport, StdoutTransport};
+
+    #[cfg(feature = "with_client_implementation")]
+    pub use crate::multiplex::{MultiplexTransport, MultiplexTransportFactory};
+
     #[cfg(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))]
-    pub use crate::transport::{DefaultTransportFactory, HttpTransport};
+    pub use crate::transport::HttpTransport;
+
+    #[cfg(any(
+        feature = "with_reqwest_transport",
+        feature = "with_curl_transport",
+        feature = "with_tokio_transport"
+    ))]
+    pub use crate::ratelimit::RateLimiter;
 
     #[cfg(feature = "with_reqwest_transport")]
     pub use crate::transport::ReqwestHttpTransport;
 
     #[cfg(feature = "with_curl_transport")]
     pub use crate::transport::CurlHttpTransport;
+
+    #[cfg(feature = "with_tokio_transport")]
+    pub use crate::tokio_transport::TokioHttpTransport;
 }
 
 #[cfg(feature = "with_client_implementation")]
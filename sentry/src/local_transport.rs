@@ -0,0 +1,293 @@
+use std::env;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use sentry_core::sentry_debug;
+use serde_json::{json, Value};
+
+use crate::internals::Transport;
+use crate::protocol::{AttachmentData, Envelope, EnvelopeItem, Event};
+
+/// The DSN used when a local transport is selected but no DSN is configured.
+///
+/// The client stays disabled without a DSN, so one is required even though
+/// local transports never talk to a server.
+pub(crate) const LOCAL_TRANSPORT_DSN: &str = "https://public@sentry.invalid/1";
+
+/// The format a `FileTransport` writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileFormat {
+    /// Every envelope in the format it would be sent to Sentry in.
+    Envelope,
+    /// One JSON object per envelope item and line.
+    ///
+    /// Each line has the item type under `type` and its payload under
+    /// `payload`.  Attachments are only described, their contents are not
+    /// written.
+    JsonLines,
+}
+
+impl FileFormat {
+    /// Picks the format from the extension of a path.
+    ///
+    /// Paths ending in `.jsonl`, `.ndjson` or `.json` get `JsonLines`, all
+    /// other paths get `Envelope`.
+    pub fn from_path(path: &Path) -> FileFormat {
+        match path.extension().and_then(|ext| ext.to_str()) {
+            Some("jsonl") | Some("ndjson") | Some("json") => FileFormat::JsonLines,
+            _ => FileFormat::Envelope,
+        }
+    }
+}
+
+/// A transport that appends everything it is given to a file.
+///
+/// This is useful to see what would be sent to Sentry, for instance while
+/// developing locally or in CI.  Writes happen synchronously on the calling
+/// thread.
+///
+/// The transport can also be selected by setting the `SENTRY_TRANSPORT`
+/// environment variable to `file://<path>` when the `DefaultTransportFactory`
+/// is used.
+pub struct FileTransport {
+    file: Mutex<File>,
+    format: FileFormat,
+}
+
+impl FileTransport {
+    /// Opens the file at the given path for appending.
+    ///
+    /// The format is picked from the extension of the path.
+    pub fn new<P: AsRef<Path>>(path: P) -> io::Result<FileTransport> {
+        let path = path.as_ref();
+        FileTransport::with_format(path, FileFormat::from_path(path))
+    }
+
+    /// Opens the file at the given path for appending in the given format.
+    pub fn with_format<P: AsRef<Path>>(path: P, format: FileFormat) -> io::Result<FileTransport> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(FileTransport {
+            file: Mutex::new(file),
+            format,
+        })
+    }
+}
+
+impl Transport for FileTransport {
+    fn send_event(&self, event: Event<'static>) {
+        self.send_envelope(event.into())
+    }
+
+    fn send_envelope(&self, envelope: Envelope) {
+        let mut buf = Vec::new();
+        let rv = match self.format {
+            FileFormat::Envelope => envelope.to_writer(&mut buf),
+            FileFormat::JsonLines => write_json_lines(&envelope, &mut buf),
+        };
+        if let Err(err) = rv.and_then(|_| self.file.lock().unwrap().write_all(&buf)) {
+            sentry_debug!("Failed to write envelope to file: {}", err);
+        }
+    }
+
+    fn flush(&self, timeout: std::time::Duration) -> bool {
+        let _timeout = timeout;
+        self.file.lock().unwrap().sync_data().is_ok()
+    }
+}
+
+/// A transport that pretty-prints everything it is given to stdout.
+///
+/// Every envelope item is printed as indented JSON below a line naming the
+/// item type.  The transport can also be selected by setting the
+/// `SENTRY_TRANSPORT` environment variable to `stdout` when the
+/// `DefaultTransportFactory` is used.
+#[derive(Debug, Default)]
+pub struct StdoutTransport;
+
+impl StdoutTransport {
+    /// Creates a new stdout transport.
+    pub fn new() -> StdoutTransport {
+        StdoutTransport
+    }
+}
+
+impl Transport for StdoutTransport {
+    fn send_event(&self, event: Event<'static>) {
+        self.send_envelope(event.into())
+    }
+
+    fn send_envelope(&self, envelope: Envelope) {
+        let mut buf = Vec::new();
+        if let Err(err) =
+            write_pretty(&envelope, &mut buf).and_then(|_| io::stdout().lock().write_all(&buf))
+        {
+            sentry_debug!("Failed to print envelope: {}", err);
+        }
+    }
+}
+
+/// A local transport selected with the `SENTRY_TRANSPORT` environment variable.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum LocalTarget {
+    File(PathBuf),
+    Stdout,
+}
+
+impl LocalTarget {
+    /// Reads the target from the `SENTRY_TRANSPORT` environment variable.
+    pub fn from_env() -> Option<LocalTarget> {
+        let value = env::var("SENTRY_TRANSPORT").ok()?;
+        let target = LocalTarget::parse(&value);
+        if target.is_none() {
+            sentry_debug!("Ignoring unknown SENTRY_TRANSPORT {:?}", value);
+        }
+        target
+    }
+
+    /// Parses `stdout` or `file://<path>`.
+    pub fn parse(value: &str) -> Option<LocalTarget> {
+        match value {
+            "stdout" | "stdout://" => Some(LocalTarget::Stdout),
+            _ if value.starts_with("file://") && value.len() > "file://".len() => {
+                Some(LocalTarget::File(value["file://".len()..].into()))
+            }
+            _ => None,
+        }
+    }
+
+    /// Creates the transport for the target.
+    ///
+    /// If the file cannot be opened this falls back to printing to stdout.
+    pub fn create_transport(&self) -> Arc<dyn Transport> {
+        match *self {
+            LocalTarget::File(ref path) => match FileTransport::new(path) {
+                Ok(transport) => return Arc::new(transport),
+                Err(err) => {
+                    sentry_debug!("Failed to open {}: {}", path.display(), err);
+                }
+            },
+            LocalTarget::Stdout => {}
+        }
+        Arc::new(StdoutTransport)
+    }
+}
+
+fn item_payload(item: &EnvelopeItem) -> serde_json::Result<Value> {
+    match *item {
+        EnvelopeItem::Event(ref event) => serde_json::to_value(event),
+        EnvelopeItem::SessionUpdate(ref session) => serde_json::to_value(session),
+        EnvelopeItem::SessionAggregates(ref aggregates) => serde_json::to_value(aggregates),
+        EnvelopeItem::Transaction(ref transaction) => serde_json::to_value(transaction),
+        EnvelopeItem::UserReport(ref report) => serde_json::to_value(report),
+        EnvelopeItem::ClientReport(ref report) => serde_json::to_value(report),
+        EnvelopeItem::Attachment(ref attachment) => {
+            let mut payload = json!({
+                "filename": attachment.filename,
+                "content_type": attachment.content_type,
+                "attachment_type": attachment.ty.map(|ty| ty.as_str()),
+            });
+            match attachment.data {
+                AttachmentData::Bytes(ref bytes) => payload["size"] = bytes.len().into(),
+                AttachmentData::Path(ref path) => {
+                    payload["path"] = path.display().to_string().into()
+                }
+            }
+            Ok(payload)
+        }
+    }
+}
+
+fn write_json_lines<W: Write>(envelope: &Envelope, mut writer: W) -> io::Result<()> {
+    for item in envelope.items() {
+        let line = json!({
+            "type": item.type_name(),
+            "payload": item_payload(item)?,
+        });
+        serde_json::to_writer(&mut writer, &line)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+fn write_pretty<W: Write>(envelope: &Envelope, mut writer: W) -> io::Result<()> {
+    for item in envelope.items() {
+        writeln!(writer, "--- sentry {} ---", item.type_name())?;
+        serde_json::to_writer_pretty(&mut writer, &item_payload(item)?)?;
+        writer.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::protocol::{Attachment, Event};
+
+    fn envelope() -> Envelope {
+        let mut envelope = Envelope::from(Event {
+            message: Some("Hello World!".into()),
+            ..Default::default()
+        });
+        envelope.add_item(Attachment {
+            data: b"contents".to_vec().into(),
+            filename: "file.txt".into(),
+            ..Default::default()
+        });
+        envelope
+    }
+
+    #[test]
+    fn test_parse_target() {
+        assert_eq!(LocalTarget::parse("stdout"), Some(LocalTarget::Stdout));
+        assert_eq!(
+            LocalTarget::parse("file:///tmp/sentry.jsonl"),
+            Some(LocalTarget::File("/tmp/sentry.jsonl".into()))
+        );
+        assert_eq!(LocalTarget::parse("file://"), None);
+        assert_eq!(LocalTarget::parse("https://public@sentry.io/1"), None);
+    }
+
+    #[test]
+    fn test_file_format() {
+        assert_eq!(
+            FileFormat::from_path(Path::new("sentry.jsonl")),
+            FileFormat::JsonLines
+        );
+        assert_eq!(
+            FileFormat::from_path(Path::new("sentry.envelopes")),
+            FileFormat::Envelope
+        );
+    }
+
+    #[test]
+    fn test_json_lines() {
+        let mut buf = Vec::new();
+        write_json_lines(&envelope(), &mut buf).unwrap();
+        let lines: Vec<Value> = buf
+            .split(|b| *b == b'\n')
+            .filter(|line| !line.is_empty())
+            .map(|line| serde_json::from_slice(line).unwrap())
+            .collect();
+
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["type"], "event");
+        assert_eq!(lines[0]["payload"]["message"], "Hello World!");
+        assert_eq!(lines[1]["type"], "attachment");
+        assert_eq!(lines[1]["payload"]["filename"], "file.txt");
+        assert_eq!(lines[1]["payload"]["size"], 8);
+    }
+
+    #[test]
+    fn test_pretty() {
+        let mut buf = Vec::new();
+        write_pretty(&envelope(), &mut buf).unwrap();
+        let output = String::from_utf8(buf).unwrap();
+
+        assert!(output.starts_with("--- sentry event ---\n{\n"));
+        assert!(output.contains("  \"message\": \"Hello World!\""));
+        assert!(output.contains("--- sentry attachment ---\n"));
+    }
+}
@@ -0,0 +1,227 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::internals::{Dsn, Transport, TransportFactory};
+use crate::protocol::{Envelope, EnvelopeItem, Event};
+use crate::ClientOptions;
+
+type Filter = Arc<dyn Fn(&EnvelopeItem) -> bool + Send + Sync>;
+
+#[derive(Clone)]
+struct DestinationConfig {
+    dsn: Dsn,
+    factory: Arc<dyn TransportFactory>,
+    filter: Option<Filter>,
+}
+
+struct Destination {
+    transport: Arc<dyn Transport>,
+    filter: Option<Filter>,
+}
+
+/// Creates a `MultiplexTransport` sending to several DSNs.
+///
+/// Every destination is a DSN together with the factory of the transport
+/// sending to it.  The factories are handed a copy of the client options
+/// with the DSN replaced, so every destination has a transport of its own
+/// with its own authentication and rate limits.  If a `spool_dir` is
+/// configured every destination spools to a subdirectory of its own.
+///
+/// The client is only enabled if its options have a DSN, which is usually
+/// the DSN of one of the destinations.  Envelopes are only ever sent to the
+/// destinations though.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use sentry::transports::{DefaultTransportFactory, MultiplexTransportFactory};
+///
+/// let dsn: sentry::internals::Dsn = "https://public@sentry.example.com/1".parse().unwrap();
+/// let legacy = "https://public@legacy.example.com/1".parse().unwrap();
+/// let factory = MultiplexTransportFactory::new()
+///     .add_destination(dsn.clone(), DefaultTransportFactory)
+///     .add_filtered_destination(legacy, DefaultTransportFactory, |item| {
+///         item.type_name() == "event"
+///     });
+///
+/// let options = sentry::ClientOptions {
+///     dsn: Some(dsn),
+///     transport: Some(Arc::new(factory)),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Clone, Default)]
+pub struct MultiplexTransportFactory {
+    destinations: Vec<DestinationConfig>,
+}
+
+impl MultiplexTransportFactory {
+    /// Creates a factory without destinations.
+    pub fn new() -> MultiplexTransportFactory {
+        MultiplexTransportFactory::default()
+    }
+
+    /// Adds a destination receiving all envelopes.
+    pub fn add_destination<F>(mut self, dsn: Dsn, factory: F) -> Self
+    where
+        F: TransportFactory + 'static,
+    {
+        self.destinations.push(DestinationConfig {
+            dsn,
+            factory: Arc::new(factory),
+            filter: None,
+        });
+        self
+    }
+
+    /// Adds a destination only receiving the envelope items matching a filter.
+    ///
+    /// Attachments are dropped together with the event they belong to and
+    /// envelopes without matching items are not sent at all.
+    pub fn add_filtered_destination<F, P>(mut self, dsn: Dsn, factory: F, filter: P) -> Self
+    where
+        F: TransportFactory + 'static,
+        P: Fn(&EnvelopeItem) -> bool + Send + Sync + 'static,
+    {
+        self.destinations.push(DestinationConfig {
+            dsn,
+            factory: Arc::new(factory),
+            filter: Some(Arc::new(filter)),
+        });
+        self
+    }
+}
+
+impl TransportFactory for MultiplexTransportFactory {
+    fn create_transport(&self, options: &ClientOptions) -> Arc<dyn Transport> {
+        Arc::new(MultiplexTransport::new(options, self))
+    }
+}
+
+/// A transport sending every envelope to several destinations.
+///
+/// This is created by the `MultiplexTransportFactory`.  Data discarded by the
+/// client itself is reported to all destinations, data discarded by the
+/// transport of a destination only to that destination.
+pub struct MultiplexTransport {
+    destinations: Vec<Destination>,
+    send_client_reports: bool,
+}
+
+impl MultiplexTransport {
+    /// Creates the transports of all destinations of the factory.
+    pub fn new(options: &ClientOptions, factory: &MultiplexTransportFactory) -> MultiplexTransport {
+        let destinations = factory
+            .destinations
+            .iter()
+            .map(|config| {
+                let options = ClientOptions {
+                    dsn: Some(config.dsn.clone()),
+                    spool_dir: options
+                        .spool_dir
+                        .as_ref()
+                        .map(|dir| destination_spool_dir(dir, &config.dsn)),
+                    ..options.clone()
+                };
+                Destination {
+                    transport: config.factory.create_transport(&options),
+                    filter: config.filter.clone(),
+                }
+            })
+            .collect();
+        MultiplexTransport {
+            destinations,
+            send_client_reports: options.send_client_reports,
+        }
+    }
+
+    fn flush_client_reports(&self) {
+        if !self.send_client_reports {
+            return;
+        }
+        for destination in &self.destinations {
+            destination.flush_client_reports();
+        }
+    }
+}
+
+/// Returns the spool directory of a destination.
+///
+/// Every destination spools to a directory of its own, so spooled envelopes
+/// are only replayed to the installation they were meant for.
+fn destination_spool_dir(dir: &Path, dsn: &Dsn) -> PathBuf {
+    let name: String = format!("{}-{}-{}", dsn.host(), dsn.port(), dsn.project_id())
+        .chars()
+        .map(|c| match c {
+            'a'..='z' | 'A'..='Z' | '0'..='9' | '.' | '-' => c,
+            _ => '_',
+        })
+        .collect();
+    dir.join(name)
+}
+
+impl Destination {
+    /// Sends the report of the data the destination discarded on its own.
+    fn flush_client_reports(&self) {
+        if let Some(report) = self
+            .transport
+            .client_reports()
+            .and_then(|recorder| recorder.take_report())
+        {
+            self.transport.send_envelope(report.into());
+        }
+    }
+}
+
+impl Transport for MultiplexTransport {
+    fn send_event(&self, event: Event<'static>) {
+        self.send_envelope(event.into())
+    }
+
+    fn send_envelope(&self, envelope: Envelope) {
+        // the reports of the destinations are sent along with the periodic
+        // reports of the client
+        // `matches!` is not available on all supported compilers
+        #[allow(clippy::match_like_matches_macro)]
+        let is_report = self.send_client_reports
+            && envelope.items().any(|item| match item {
+                EnvelopeItem::ClientReport(..) => true,
+                _ => false,
+            });
+        for destination in &self.destinations {
+            if is_report {
+                destination.flush_client_reports();
+            }
+            let envelope = match destination.filter {
+                Some(ref filter) => match envelope.clone().filter(|item| filter(item)) {
+                    Some(envelope) => envelope,
+                    None => continue,
+                },
+                None => envelope.clone(),
+            };
+            destination.transport.send_envelope(envelope);
+        }
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        self.flush_client_reports();
+        let deadline = Instant::now() + timeout;
+        self.destinations.iter().fold(true, |flushed, destination| {
+            let timeout = deadline.saturating_duration_since(Instant::now());
+            destination.transport.flush(timeout) && flushed
+        })
+    }
+
+    fn shutdown(&self, timeout: Duration) -> bool {
+        self.flush_client_reports();
+        let deadline = Instant::now() + timeout;
+        self.destinations
+            .iter()
+            .fold(true, |shut_down, destination| {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+                destination.transport.shutdown(timeout) && shut_down
+            })
+    }
+}
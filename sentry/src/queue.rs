@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+use std::sync::{Condvar, Mutex};
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    test
+))]
+use std::time::Duration;
+use std::time::Instant;
+
+use crate::QueueOverflow;
+
+/// The error returned when an item could not be pushed onto the queue.
+#[derive(Debug, PartialEq)]
+pub(crate) enum PushError<T> {
+    /// The queue was full and the contained item was dropped.
+    ///
+    /// Depending on the overflow policy this is either the new item or the
+    /// oldest item in the queue.
+    Full(T),
+    /// The queue was closed.
+    Closed(T),
+}
+
+/// The result of taking an item off the queue.
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    test
+))]
+#[derive(Debug, PartialEq)]
+pub(crate) enum Pop<T> {
+    Item(T),
+    Timeout,
+    Closed,
+}
+
+struct Inner<T> {
+    items: VecDeque<T>,
+    closed: bool,
+}
+
+/// A bounded queue between a transport and its send loop.
+///
+/// When the queue is full new items are handled according to the
+/// `QueueOverflow` policy.  Once the queue is closed no new items are
+/// accepted, but the items already queued can still be taken off.
+pub(crate) struct Queue<T> {
+    inner: Mutex<Inner<T>>,
+    capacity: usize,
+    overflow: QueueOverflow,
+    not_empty: Condvar,
+    not_full: Condvar,
+    #[cfg(feature = "with_tokio_transport")]
+    notify: tokio::sync::Notify,
+}
+
+impl<T> Queue<T> {
+    /// Creates a new queue.
+    pub fn new(capacity: usize, overflow: QueueOverflow) -> Self {
+        Queue {
+            inner: Mutex::new(Inner {
+                items: VecDeque::with_capacity(capacity),
+                closed: false,
+            }),
+            capacity,
+            overflow,
+            not_empty: Condvar::new(),
+            not_full: Condvar::new(),
+            #[cfg(feature = "with_tokio_transport")]
+            notify: tokio::sync::Notify::new(),
+        }
+    }
+
+    /// Pushes an item, applying the overflow policy if the queue is full.
+    pub fn push(&self, item: T) -> Result<(), PushError<T>> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closed {
+            return Err(PushError::Closed(item));
+        }
+        if inner.items.len() >= self.capacity {
+            match self.overflow {
+                QueueOverflow::DropNewest => return Err(PushError::Full(item)),
+                QueueOverflow::DropOldest => {
+                    let oldest = match inner.items.pop_front() {
+                        Some(oldest) => oldest,
+                        None => return Err(PushError::Full(item)),
+                    };
+                    inner.items.push_back(item);
+                    drop(inner);
+                    self.notify_pushed();
+                    return Err(PushError::Full(oldest));
+                }
+                QueueOverflow::Block(timeout) => {
+                    let deadline = Instant::now() + timeout;
+                    while !inner.closed && inner.items.len() >= self.capacity {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            return Err(PushError::Full(item));
+                        }
+                        inner = self.not_full.wait_timeout(inner, deadline - now).unwrap().0;
+                    }
+                    if inner.closed {
+                        return Err(PushError::Closed(item));
+                    }
+                }
+            }
+        }
+        inner.items.push_back(item);
+        drop(inner);
+        self.notify_pushed();
+        Ok(())
+    }
+
+    /// Pushes an item regardless of the capacity of the queue.
+    ///
+    /// This is used for control messages which must never be dropped.
+    #[cfg(any(feature = "with_tokio_transport", test))]
+    pub fn force_push(&self, item: T) -> Result<(), PushError<T>> {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.closed {
+            return Err(PushError::Closed(item));
+        }
+        inner.items.push_back(item);
+        drop(inner);
+        self.notify_pushed();
+        Ok(())
+    }
+
+    /// Takes the oldest item off the queue.
+    ///
+    /// Blocks until an item is available, the timeout elapsed or the queue
+    /// was closed and is empty.
+    #[cfg(any(
+        feature = "with_reqwest_transport",
+        feature = "with_curl_transport",
+        test
+    ))]
+    pub fn pop(&self, timeout: Option<Duration>) -> Pop<T> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut inner = self.inner.lock().unwrap();
+        loop {
+            if let Some(item) = inner.items.pop_front() {
+                self.not_full.notify_one();
+                return Pop::Item(item);
+            }
+            if inner.closed {
+                return Pop::Closed;
+            }
+            inner = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return Pop::Timeout;
+                    }
+                    self.not_empty
+                        .wait_timeout(inner, deadline - now)
+                        .unwrap()
+                        .0
+                }
+                None => self.not_empty.wait(inner).unwrap(),
+            };
+        }
+    }
+
+    /// Takes the oldest item off the queue without blocking the thread.
+    ///
+    /// Returns `None` once the queue was closed and is empty.
+    #[cfg(feature = "with_tokio_transport")]
+    pub async fn pop_async(&self) -> Option<T> {
+        loop {
+            {
+                let mut inner = self.inner.lock().unwrap();
+                if let Some(item) = inner.items.pop_front() {
+                    self.not_full.notify_one();
+                    return Some(item);
+                }
+                if inner.closed {
+                    return None;
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+
+    /// Takes all items off the queue.
+    #[cfg(any(
+        feature = "with_reqwest_transport",
+        feature = "with_curl_transport",
+        test
+    ))]
+    pub fn drain(&self) -> Vec<T> {
+        let items = self.inner.lock().unwrap().items.drain(..).collect();
+        self.not_full.notify_all();
+        items
+    }
+
+    /// Closes the queue for new items.
+    pub fn close(&self) {
+        self.inner.lock().unwrap().closed = true;
+        self.not_full.notify_all();
+        self.notify_pushed();
+    }
+
+    fn notify_pushed(&self) {
+        self.not_empty.notify_one();
+        #[cfg(feature = "with_tokio_transport")]
+        self.notify.notify();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::sync::Arc;
+    use std::thread;
+
+    #[test]
+    fn test_drop_newest() {
+        let queue = Queue::new(2, QueueOverflow::DropNewest);
+        assert!(queue.push(1).is_ok());
+        assert!(queue.push(2).is_ok());
+        assert_eq!(queue.push(3), Err(PushError::Full(3)));
+        assert_eq!(queue.drain(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let queue = Queue::new(2, QueueOverflow::DropOldest);
+        assert!(queue.push(1).is_ok());
+        assert!(queue.push(2).is_ok());
+        assert_eq!(queue.push(3), Err(PushError::Full(1)));
+        assert_eq!(queue.drain(), vec![2, 3]);
+    }
+
+    #[test]
+    fn test_block() {
+        let queue = Arc::new(Queue::new(
+            1,
+            QueueOverflow::Block(Duration::from_millis(50)),
+        ));
+        assert!(queue.push(1).is_ok());
+        let start = Instant::now();
+        assert_eq!(queue.push(2), Err(PushError::Full(2)));
+        assert!(start.elapsed() >= Duration::from_millis(50));
+
+        // the push goes through once the item is taken off in time
+        let consumer = {
+            let queue = queue.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(10));
+                queue.pop(None) == Pop::Item(1)
+            })
+        };
+        assert!(queue.push(3).is_ok());
+        assert!(consumer.join().unwrap());
+        assert_eq!(queue.drain(), vec![3]);
+    }
+
+    #[test]
+    fn test_close() {
+        let queue = Queue::new(1, QueueOverflow::DropNewest);
+        assert!(queue.push(1).is_ok());
+        assert!(queue.force_push(2).is_ok());
+        queue.close();
+        assert_eq!(queue.push(3), Err(PushError::Closed(3)));
+        assert_eq!(queue.pop(None), Pop::Item(1));
+        assert_eq!(queue.pop(None), Pop::Item(2));
+        assert_eq!(queue.pop(None), Pop::Closed);
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+use httpdate::parse_http_date;
+use sentry_core::sentry_debug;
+
+use crate::protocol::{DataCategory, Envelope};
+
+/// The time sentry is assumed to be rate limiting after a 429 without headers.
+const DEFAULT_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// The longest rate limit accepted from a header, longer ones are shortened.
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// Returns the time a limit of the given number of seconds expires.
+///
+/// Negative and invalid values expire right away and absurdly large values
+/// are clamped to `MAX_RETRY_AFTER`.
+fn expires_after(seconds: f64) -> SystemTime {
+    let seconds = seconds
+        .ceil()
+        .max(0.0)
+        .min(MAX_RETRY_AFTER.as_secs() as f64);
+    SystemTime::now() + Duration::from_secs(seconds as u64)
+}
+
+/// Keeps track of the rate limits sentry communicates to the client.
+///
+/// Rate limits are read from the `X-Sentry-Rate-Limits` and `Retry-After`
+/// response headers and apply either to all data or to individual data
+/// categories.  Envelope items of a limited category are dropped until the
+/// limit expires.
+#[derive(Debug, Default, Clone)]
+pub struct RateLimiter {
+    global: Option<SystemTime>,
+    categories: HashMap<DataCategory, SystemTime>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter without any limits.
+    pub fn new() -> RateLimiter {
+        RateLimiter::default()
+    }
+
+    /// Updates the limits from a `Retry-After` header.
+    ///
+    /// The header holds either a number of seconds or an HTTP date and
+    /// limits all categories.
+    pub fn update_from_retry_after(&mut self, header: &str) {
+        let header = header.trim();
+        let until = if let Ok(value) = header.parse::<f64>() {
+            expires_after(value)
+        } else if let Ok(value) = parse_http_date(header) {
+            value
+        } else {
+            SystemTime::now() + DEFAULT_RETRY_AFTER
+        };
+        self.global = Some(until);
+    }
+
+    /// Updates the limits from an `X-Sentry-Rate-Limits` header.
+    ///
+    /// The header is a comma separated list of limits in the form
+    /// `retry_after:categories:scope:reason`, where `categories` is a
+    /// semicolon separated list.  A limit without categories applies to all
+    /// data.  Unknown categories are ignored.
+    pub fn update_from_sentry_header(&mut self, header: &str) {
+        for limit in header.split(',') {
+            let mut parts = limit.trim().split(':');
+            let seconds = match parts.next().and_then(|x| x.trim().parse::<f64>().ok()) {
+                Some(seconds) => seconds,
+                None => continue,
+            };
+            let until = expires_after(seconds);
+            let categories = parts.next().unwrap_or("").trim();
+            if categories.is_empty() {
+                self.global = Some(until);
+                continue;
+            }
+            for category in categories.split(';') {
+                if let Ok(category) = category.parse::<DataCategory>() {
+                    self.categories.insert(category, until);
+                }
+            }
+        }
+    }
+
+    /// Updates the limits after a 429 response without rate limit headers.
+    pub fn update_from_429(&mut self) {
+        self.global = Some(SystemTime::now() + DEFAULT_RETRY_AFTER);
+    }
+
+    /// Returns the time left until data of the given category may be sent
+    /// again, or `None` if the category is not limited.
+    pub fn is_disabled(&self, category: DataCategory) -> Option<Duration> {
+        let now = SystemTime::now();
+        let time_left = |until: &SystemTime| until.duration_since(now).ok();
+        let global = self.global.as_ref().and_then(time_left);
+        let category = self.categories.get(&category).and_then(time_left);
+        global.max(category)
+    }
+
+    /// Removes all items of limited categories from the envelope.
+    ///
+    /// Returns `None` if no items remain.
+    pub fn filter_envelope(&self, envelope: Envelope) -> Option<Envelope> {
+        envelope.filter(|item| match self.is_disabled(item.data_category()) {
+            Some(time_left) => {
+                sentry_debug!(
+                    "Skipping {} item because we're disabled due to rate limits for {}s",
+                    item.type_name(),
+                    time_left.as_secs()
+                );
+                false
+            }
+            None => true,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::internals::{Utc, Uuid};
+    use crate::protocol::{Attachment, Event, SessionAttributes, SessionUpdate};
+
+    #[test]
+    fn test_sentry_header() {
+        let mut rate_limiter = RateLimiter::new();
+        rate_limiter
+            .update_from_sentry_header("120:error:project:reason, 60:session;foo:organization");
+
+        assert!(rate_limiter.is_disabled(DataCategory::Error).unwrap() > Duration::from_secs(60));
+        assert!(
+            rate_limiter.is_disabled(DataCategory::Session).unwrap() <= Duration::from_secs(60)
+        );
+        assert!(rate_limiter
+            .is_disabled(DataCategory::Transaction)
+            .is_none());
+
+        rate_limiter.update_from_sentry_header("30::organization");
+        assert!(rate_limiter
+            .is_disabled(DataCategory::Transaction)
+            .is_some());
+        assert!(rate_limiter.is_disabled(DataCategory::Error).unwrap() > Duration::from_secs(60));
+    }
+
+    #[test]
+    fn test_retry_after() {
+        let mut rate_limiter = RateLimiter::new();
+        rate_limiter.update_from_retry_after("60");
+        assert!(rate_limiter.is_disabled(DataCategory::Error).is_some());
+        assert!(rate_limiter.is_disabled(DataCategory::Attachment).is_some());
+
+        let mut rate_limiter = RateLimiter::new();
+        rate_limiter.update_from_retry_after("Thu, 01 Jan 1970 00:00:00 GMT");
+        assert!(rate_limiter.is_disabled(DataCategory::Error).is_none());
+    }
+
+    #[test]
+    fn test_absurd_values() {
+        let mut rate_limiter = RateLimiter::new();
+        rate_limiter.update_from_sentry_header("1e30:error, NaN:session, -5:transaction");
+        let time_left = rate_limiter.is_disabled(DataCategory::Error).unwrap();
+        assert!(time_left > Duration::from_secs(60) && time_left <= MAX_RETRY_AFTER);
+        assert!(rate_limiter.is_disabled(DataCategory::Session).is_none());
+        assert!(rate_limiter
+            .is_disabled(DataCategory::Transaction)
+            .is_none());
+
+        rate_limiter.update_from_retry_after("1e300");
+        assert!(rate_limiter.is_disabled(DataCategory::Error).unwrap() <= MAX_RETRY_AFTER);
+    }
+
+    #[test]
+    fn test_filter_envelope() {
+        let mut rate_limiter = RateLimiter::new();
+        rate_limiter.update_from_sentry_header("60:error");
+
+        let mut envelope = Envelope::from(Event::default());
+        envelope.add_item(Attachment::from_bytes("file.txt", b"data".to_vec()));
+        envelope.add_item(SessionUpdate {
+            session_id: Uuid::new_v4(),
+            distinct_id: None,
+            sequence: None,
+            timestamp: None,
+            started: Utc::now(),
+            init: true,
+            duration: None,
+            status: Default::default(),
+            errors: 0,
+            attributes: SessionAttributes {
+                release: "app@1.0".into(),
+                environment: None,
+                ip_address: None,
+                user_agent: None,
+            },
+        });
+
+        // the attachment goes away together with its event
+        let envelope = rate_limiter.filter_envelope(envelope).unwrap();
+        let items: Vec<_> = envelope.items().map(|item| item.type_name()).collect();
+        assert_eq!(items, vec!["session"]);
+
+        rate_limiter.update_from_sentry_header("60:session");
+        assert!(rate_limiter.filter_envelope(envelope).is_none());
+    }
+}
@@ -0,0 +1,46 @@
+//! Running rayon tasks that keep the current scope.
+//!
+//! **Feature:** `with_rayon` (*disabled by default*)
+//!
+//! Tasks run on the threads of the rayon thread pool, which use a hub based
+//! on `Hub::main()`.  The functions in this module run tasks with a hub based
+//! on the current one instead, see `Hub::bind_to_closure`.
+//!
+//! # Example
+//!
+//! ```
+//! sentry::configure_scope(|scope| scope.set_tag("request", "42"));
+//! let (left, right) = sentry::rayon::join(
+//!     || {
+//!         // events captured here are tagged with `request`
+//!         1
+//!     },
+//!     || 2,
+//! );
+//! assert_eq!(left + right, 3);
+//! ```
+use crate::Hub;
+
+/// Spawns a task on the global thread pool with a hub based on the current hub.
+///
+/// This works like `rayon::spawn`.
+pub fn spawn<F>(f: F)
+where
+    F: FnOnce() + Send + 'static,
+{
+    ::rayon::spawn(Hub::bind_to_closure(f))
+}
+
+/// Runs two closures, potentially in parallel, each with a hub based on the
+/// current hub.
+///
+/// This works like `rayon::join`.
+pub fn join<A, B, RA, RB>(a: A, b: B) -> (RA, RB)
+where
+    A: FnOnce() -> RA + Send,
+    B: FnOnce() -> RB + Send,
+    RA: Send,
+    RB: Send,
+{
+    ::rayon::join(Hub::bind_to_closure(a), Hub::bind_to_closure(b))
+}
@@ -0,0 +1,167 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use sentry_core::sentry_debug;
+
+use crate::internals::{ClientReportRecorder, Uuid};
+use crate::protocol::{DiscardReason, Envelope};
+use crate::ClientOptions;
+
+/// A directory in which envelopes that could not be delivered are kept.
+///
+/// Every envelope is stored in a file of its own.  The file names start with
+/// the time the envelope was spooled, so sorting them by name yields the
+/// oldest envelope first.
+#[derive(Debug)]
+pub(crate) struct Spool {
+    dir: PathBuf,
+    max_size: u64,
+    max_age: Duration,
+    client_reports: Arc<ClientReportRecorder>,
+}
+
+struct SpoolEntry {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+impl Spool {
+    /// Opens the spool directory configured in the options.
+    ///
+    /// The directory is created if it does not exist yet.  Envelopes that
+    /// are discarded because of the limits are recorded in `client_reports`.
+    pub fn from_options(
+        options: &ClientOptions,
+        client_reports: Arc<ClientReportRecorder>,
+    ) -> Option<Spool> {
+        let dir = options.spool_dir.clone()?;
+        if let Err(err) = fs::create_dir_all(&dir) {
+            sentry_debug!("Failed to create spool directory: {}", err);
+            return None;
+        }
+        Some(Spool {
+            dir,
+            max_size: options.spool_max_size,
+            max_age: options.spool_max_age,
+            client_reports,
+        })
+    }
+
+    /// Writes an envelope to the spool.
+    pub fn store(&self, envelope: &Envelope) {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let name = format!(
+            "{:020}-{}.envelope",
+            timestamp.as_nanos(),
+            Uuid::new_v4().to_simple()
+        );
+        // the envelope is written under a temporary name first so that a
+        // replay never picks up a partially written file.
+        let tmp_path = self.dir.join(format!(".{}.tmp", name));
+        let result = fs::write(&tmp_path, envelope.to_vec())
+            .and_then(|_| fs::rename(&tmp_path, self.dir.join(&name)));
+        match result {
+            Ok(()) => {
+                sentry_debug!("Spooled envelope to {}", name);
+            }
+            Err(err) => {
+                sentry_debug!("Failed to spool envelope: {}", err);
+                fs::remove_file(&tmp_path).ok();
+            }
+        }
+        self.enforce_limits();
+    }
+
+    /// Returns the oldest spooled envelope together with the path of its file.
+    ///
+    /// Files that are expired or cannot be parsed are discarded.
+    pub fn oldest(&self) -> Option<(PathBuf, Envelope)> {
+        for entry in self.enforce_limits() {
+            let envelope = fs::read(&entry.path)
+                .ok()
+                .and_then(|bytes| Envelope::from_slice(&bytes).ok());
+            match envelope {
+                Some(envelope) => return Some((entry.path, envelope)),
+                None => {
+                    sentry_debug!("Discarding invalid spool file {}", entry.path.display());
+                    self.remove(&entry.path);
+                }
+            }
+        }
+        None
+    }
+
+    /// Removes an envelope file from the spool.
+    pub fn remove(&self, path: &Path) {
+        fs::remove_file(path).ok();
+    }
+
+    /// Discards expired envelopes and the oldest envelopes exceeding the
+    /// size limit, returning the remaining ones oldest first.
+    fn enforce_limits(&self) -> Vec<SpoolEntry> {
+        let now = SystemTime::now();
+        let mut entries = self.entries();
+        entries.retain(|entry| {
+            let expired = now
+                .duration_since(entry.modified)
+                .map_or(false, |age| age > self.max_age);
+            if expired {
+                self.discard(&entry.path);
+            }
+            !expired
+        });
+
+        let mut total_size: u64 = entries.iter().map(|entry| entry.size).sum();
+        let mut excess = 0;
+        for entry in &entries {
+            if total_size <= self.max_size {
+                break;
+            }
+            total_size -= entry.size;
+            self.discard(&entry.path);
+            excess += 1;
+        }
+        if excess > 0 {
+            sentry_debug!("Discarded {} spooled envelopes over the size limit", excess);
+        }
+        entries.split_off(excess)
+    }
+
+    /// Removes an envelope file and records its items as discarded.
+    fn discard(&self, path: &Path) {
+        if let Some(envelope) = fs::read(path)
+            .ok()
+            .and_then(|bytes| Envelope::from_slice(&bytes).ok())
+        {
+            self.client_reports
+                .record_envelope(DiscardReason::CacheOverflow, &envelope);
+        }
+        self.remove(path);
+    }
+
+    fn entries(&self) -> Vec<SpoolEntry> {
+        let read_dir = match fs::read_dir(&self.dir) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return vec![],
+        };
+        let mut entries: Vec<_> = read_dir
+            .filter_map(Result::ok)
+            .filter(|entry| entry.file_name().to_string_lossy().ends_with(".envelope"))
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some(SpoolEntry {
+                    path: entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(UNIX_EPOCH),
+                })
+            })
+            .collect();
+        entries.sort_by(|a, b| a.path.cmp(&b.path));
+        entries
+    }
+}
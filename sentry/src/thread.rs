@@ -0,0 +1,80 @@
+//! Spawning threads that keep the current scope.
+//!
+//! A thread spawned with `std::thread` gets a hub based on `Hub::main()`, so
+//! breadcrumbs, tags and other data configured on the hub of the spawning
+//! thread are lost.  The functions in this module spawn threads that run with
+//! a hub based on the current one instead, see `Hub::bind_to_closure`.
+//!
+//! # Example
+//!
+//! ```
+//! sentry::configure_scope(|scope| scope.set_tag("worker", "worker1"));
+//! sentry::thread::spawn(|| {
+//!     // events captured here are tagged with `worker`
+//! })
+//! .join()
+//! .unwrap();
+//! ```
+use std::io;
+use std::thread::{self, JoinHandle};
+
+use crate::Hub;
+
+/// Spawns a new thread running with a hub based on the current hub.
+///
+/// This works like `std::thread::spawn`.
+pub fn spawn<F, T>(f: F) -> JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    thread::spawn(Hub::bind_to_closure(f))
+}
+
+/// A thread factory spawning threads with a hub based on the current hub.
+///
+/// This wraps `std::thread::Builder`.
+#[derive(Debug)]
+pub struct Builder {
+    inner: thread::Builder,
+}
+
+impl Default for Builder {
+    fn default() -> Builder {
+        thread::Builder::new().into()
+    }
+}
+
+impl From<thread::Builder> for Builder {
+    fn from(inner: thread::Builder) -> Builder {
+        Builder { inner }
+    }
+}
+
+impl Builder {
+    /// Creates a new thread factory.
+    pub fn new() -> Builder {
+        Builder::default()
+    }
+
+    /// Names the thread to be spawned.
+    pub fn name(self, name: String) -> Builder {
+        self.inner.name(name).into()
+    }
+
+    /// Sets the stack size of the thread to be spawned.
+    pub fn stack_size(self, size: usize) -> Builder {
+        self.inner.stack_size(size).into()
+    }
+
+    /// Spawns a new thread running with a hub based on the current hub.
+    ///
+    /// This works like `std::thread::Builder::spawn`.
+    pub fn spawn<F, T>(self, f: F) -> io::Result<JoinHandle<T>>
+    where
+        F: FnOnce() -> T + Send + 'static,
+        T: Send + 'static,
+    {
+        self.inner.spawn(Hub::bind_to_closure(f))
+    }
+}
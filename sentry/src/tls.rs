@@ -0,0 +1,201 @@
+use sentry_core::sentry_debug;
+
+use crate::{ClientIdentity, ClientOptions};
+
+/// A client identity with its data loaded.
+///
+/// Not every TLS backend supports every format, only curl reads them all.
+#[cfg_attr(not(feature = "with_curl_transport"), allow(dead_code))]
+#[derive(Clone)]
+pub(crate) enum Identity {
+    Pem { cert: Vec<u8>, key: Vec<u8> },
+    Pkcs12 { archive: Vec<u8>, password: String },
+}
+
+/// The TLS settings of the client options with all files loaded.
+///
+/// Files that cannot be read are skipped.
+#[cfg_attr(not(feature = "with_curl_transport"), allow(dead_code))]
+#[derive(Clone, Default)]
+pub(crate) struct TlsConfig {
+    pub ca_certs: Vec<Vec<u8>>,
+    pub identity: Option<Identity>,
+    pub accept_invalid_certs: bool,
+}
+
+impl TlsConfig {
+    /// Loads the TLS settings of the options.
+    pub fn from_options(options: &ClientOptions) -> TlsConfig {
+        let load = |source: &crate::TlsSource| match source.load() {
+            Ok(data) => Some(data),
+            Err(err) => {
+                sentry_debug!("Failed to load {:?}: {}", source, err);
+                None
+            }
+        };
+        let identity = options
+            .client_identity
+            .as_ref()
+            .and_then(|identity| match *identity {
+                ClientIdentity::Pem { ref cert, ref key } => Some(Identity::Pem {
+                    cert: load(cert)?,
+                    key: load(key)?,
+                }),
+                ClientIdentity::Pkcs12 {
+                    ref archive,
+                    ref password,
+                } => Some(Identity::Pkcs12 {
+                    archive: load(archive)?,
+                    password: password.clone(),
+                }),
+            });
+        TlsConfig {
+            ca_certs: options.ca_certs.iter().filter_map(load).collect(),
+            identity,
+            accept_invalid_certs: options.accept_invalid_certs,
+        }
+    }
+
+    /// Applies the settings to a curl handle.
+    ///
+    /// This has to happen again after every reset of the handle.
+    #[cfg(feature = "with_curl_transport")]
+    pub fn apply_to_curl(&self, handle: &mut curl::easy::Easy) -> Result<(), curl::Error> {
+        if !self.ca_certs.is_empty() {
+            handle.ssl_cainfo_blob(&self.ca_certs.join(&b'\n'))?;
+        }
+        match self.identity {
+            Some(Identity::Pem { ref cert, ref key }) => {
+                handle.ssl_cert_blob(cert)?;
+                handle.ssl_cert_type("PEM")?;
+                handle.ssl_key_blob(key)?;
+                handle.ssl_key_type("PEM")?;
+            }
+            Some(Identity::Pkcs12 {
+                ref archive,
+                ref password,
+            }) => {
+                handle.ssl_cert_blob(archive)?;
+                handle.ssl_cert_type("P12")?;
+                handle.key_password(password)?;
+            }
+            None => {}
+        }
+        if self.accept_invalid_certs {
+            handle.ssl_verify_peer(false)?;
+            handle.ssl_verify_host(false)?;
+        }
+        Ok(())
+    }
+}
+
+/// Applies the TLS settings to a reqwest client builder.
+#[cfg(any(feature = "with_reqwest_transport", feature = "with_tokio_transport"))]
+pub(crate) trait ConfigureTls: Sized {
+    /// Returns the builder with the settings applied.
+    fn configure_tls(self, config: &TlsConfig) -> Self;
+}
+
+#[cfg(any(feature = "with_reqwest_transport", feature = "with_tokio_transport"))]
+macro_rules! implement_configure_tls {
+    ($builder:ty) => {
+        impl ConfigureTls for $builder {
+            #[cfg(any(feature = "with_native_tls", feature = "with_rustls"))]
+            fn configure_tls(mut self, config: &TlsConfig) -> Self {
+                for cert in &config.ca_certs {
+                    match reqwest::Certificate::from_pem(cert) {
+                        Ok(cert) => self = self.add_root_certificate(cert),
+                        Err(err) => {
+                            sentry_debug!("Invalid CA certificate: {}", err);
+                        }
+                    }
+                }
+                if let Some(ref identity) = config.identity {
+                    match reqwest_identity(identity) {
+                        Ok(identity) => self = self.identity(identity),
+                        Err(err) => {
+                            sentry_debug!("Invalid client identity: {}", err);
+                        }
+                    }
+                }
+                self.danger_accept_invalid_certs(config.accept_invalid_certs)
+            }
+
+            #[cfg(not(any(feature = "with_native_tls", feature = "with_rustls")))]
+            fn configure_tls(self, config: &TlsConfig) -> Self {
+                let _config = config;
+                self
+            }
+        }
+    };
+}
+
+#[cfg(feature = "with_reqwest_transport")]
+implement_configure_tls!(reqwest::blocking::ClientBuilder);
+#[cfg(feature = "with_tokio_transport")]
+implement_configure_tls!(reqwest::ClientBuilder);
+
+#[cfg(any(feature = "with_native_tls", feature = "with_rustls"))]
+fn reqwest_identity(identity: &Identity) -> Result<reqwest::Identity, String> {
+    match *identity {
+        #[cfg(feature = "with_native_tls")]
+        Identity::Pkcs12 {
+            ref archive,
+            ref password,
+        } => reqwest::Identity::from_pkcs12_der(archive, password).map_err(|e| e.to_string()),
+        #[cfg(all(feature = "with_rustls", not(feature = "with_native_tls")))]
+        Identity::Pem { ref cert, ref key } => {
+            let pem = [&cert[..], &key[..]].join(&b'\n');
+            reqwest::Identity::from_pem(&pem).map_err(|e| e.to_string())
+        }
+        _ => Err("the identity format is not supported by the TLS backend".into()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::internals::Uuid;
+    use crate::TlsSource;
+
+    #[test]
+    fn test_load() {
+        let path = std::env::temp_dir().join(format!("sentry-ca-{}.pem", Uuid::new_v4()));
+        std::fs::write(&path, b"from a file").unwrap();
+
+        let config = TlsConfig::from_options(&ClientOptions {
+            ca_certs: vec![
+                TlsSource::Path(path.clone()),
+                TlsSource::Bytes(b"from bytes".to_vec()),
+                TlsSource::Path(path.with_extension("missing")),
+            ],
+            client_identity: Some(ClientIdentity::Pem {
+                cert: TlsSource::Bytes(b"cert".to_vec()),
+                key: TlsSource::Path(path.with_extension("missing")),
+            }),
+            accept_invalid_certs: true,
+            ..Default::default()
+        });
+        std::fs::remove_file(&path).ok();
+
+        assert_eq!(
+            config.ca_certs,
+            vec![b"from a file".to_vec(), b"from bytes".to_vec()]
+        );
+        // an identity is only used if all of its parts could be loaded
+        assert!(config.identity.is_none());
+        assert!(config.accept_invalid_certs);
+    }
+
+    #[test]
+    fn test_debug_hides_secrets() {
+        let identity = ClientIdentity::Pkcs12 {
+            archive: TlsSource::Bytes(b"secret key".to_vec()),
+            password: "hunter2".into(),
+        };
+        let debug = format!("{:?}", identity);
+        assert!(!debug.contains("hunter2"));
+        assert!(debug.contains("Bytes(10 bytes)"));
+    }
+}
@@ -0,0 +1,40 @@
+//! Spawning tokio tasks with a hub of their own.
+//!
+//! **Feature:** `with_tokio` (*disabled by default*)
+//!
+//! Tasks share the threads of the runtime, so the thread local hub is not
+//! suitable to hold the scope of a task.  The tasks spawned here are bound to
+//! a new hub based on the current hub instead, see `FutureExt::fork_hub`.
+//! Scope changes made inside a task stay with the task and never leak into
+//! sibling tasks or the spawning task.
+//!
+//! # Example
+//!
+//! ```
+//! # let mut runtime = tokio::runtime::Runtime::new().unwrap();
+//! # runtime.block_on(async {
+//! sentry::configure_scope(|scope| scope.set_tag("request", "42"));
+//! sentry::tokio::spawn(async {
+//!     // events captured here are tagged with `request`
+//!     sentry::configure_scope(|scope| scope.set_transaction(Some("worker")));
+//! })
+//! .await
+//! .unwrap();
+//! # });
+//! ```
+use std::future::Future;
+
+use ::tokio::task::JoinHandle;
+
+use crate::FutureExt;
+
+/// Spawns a new task bound to a new hub based on the current hub.
+///
+/// This works like `tokio::spawn`.
+pub fn spawn<T>(task: T) -> JoinHandle<T::Output>
+where
+    T: Future + Send + 'static,
+    T::Output: Send + 'static,
+{
+    ::tokio::spawn(task.fork_hub())
+}
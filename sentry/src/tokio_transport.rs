@@ -0,0 +1,373 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{mpsc as std_mpsc, Arc};
+use std::time::Duration;
+
+use reqwest::{
+    header::{CONTENT_ENCODING, CONTENT_TYPE},
+    Client, Proxy,
+};
+use tokio::runtime::{Handle, TryCurrentError};
+use tokio::sync::oneshot;
+
+use sentry_core::sentry_debug;
+
+use crate::compression::compress;
+use crate::internals::{ClientReportRecorder, Dsn, Transport};
+use crate::protocol::{DiscardReason, Envelope, Event};
+use crate::queue::{PushError, Queue};
+use crate::spool::Spool;
+use crate::tls::{ConfigureTls, TlsConfig};
+use crate::transport::{handle_response, SendResult, SendState, SPOOL_RETRY_INTERVAL};
+use crate::{ClientOptions, Compression, QueueOverflow};
+
+enum Task {
+    Envelope(Envelope),
+    Flush(Box<dyn FnOnce() + Send>),
+}
+
+/// A transport that sends envelopes via HTTP to sentry from a tokio task.
+///
+/// Unlike the other HTTP transports this one does not spawn a thread of its
+/// own but runs its send loop as a task on an existing tokio runtime, using
+/// the async `reqwest` client.  The runtime needs the time driver enabled.
+///
+/// In async code the queue should be drained with [`flush`](#method.flush)
+/// or [`close`](#method.close).  The blocking `Transport::flush` and
+/// `Transport::shutdown` must not be called from a thread of a single
+/// threaded runtime, as the send loop cannot make progress while they wait.
+/// For the same reason a full queue never blocks: `QueueOverflow::Block` is
+/// treated as `DropNewest`.
+///
+/// This is enabled by the `with_tokio_transport` flag.
+///
+/// # Example
+///
+/// ```no_run
+/// use std::sync::Arc;
+/// use sentry::internals::Transport;
+/// use sentry::transports::TokioHttpTransport;
+///
+/// # async fn run() {
+/// let _guard = sentry::init(sentry::ClientOptions {
+///     dsn: "https://key@sentry.io/42".parse().ok(),
+///     transport: Some(Arc::new(|options: &sentry::ClientOptions| {
+///         Arc::new(TokioHttpTransport::new(options)) as Arc<dyn Transport>
+///     })),
+///     ..Default::default()
+/// });
+/// # }
+/// ```
+pub struct TokioHttpTransport {
+    queue: Arc<Queue<Task>>,
+    queue_overflows: AtomicU64,
+    spool: Option<Arc<Spool>>,
+    client_reports: Arc<ClientReportRecorder>,
+    shutting_down: Arc<AtomicBool>,
+}
+
+impl TokioHttpTransport {
+    /// Creates a new transport on the runtime of the current context.
+    ///
+    /// # Panics
+    ///
+    /// This panics if called outside of a tokio runtime.  Use
+    /// [`try_new`](#method.try_new) or [`with_handle`](#method.with_handle)
+    /// where a runtime is not guaranteed.
+    pub fn new(options: &ClientOptions) -> Self {
+        Self::new_internal(options, Handle::current(), None)
+    }
+
+    /// Creates a new transport on the runtime of the current context.
+    ///
+    /// Unlike `new` this returns an error if called outside of a tokio
+    /// runtime.
+    pub fn try_new(options: &ClientOptions) -> Result<Self, TryCurrentError> {
+        let handle = Handle::try_current()?;
+        Ok(Self::new_internal(options, handle, None))
+    }
+
+    /// Creates a new transport that runs its send loop on the given runtime.
+    pub fn with_handle(options: &ClientOptions, handle: Handle) -> Self {
+        Self::new_internal(options, handle, None)
+    }
+
+    /// Creates a new transport that uses the passed HTTP client.
+    ///
+    /// # Panics
+    ///
+    /// This panics if called outside of a tokio runtime.
+    pub fn with_client(options: &ClientOptions, client: Client) -> Self {
+        Self::new_internal(options, Handle::current(), Some(client))
+    }
+
+    fn new_internal(options: &ClientOptions, handle: Handle, client: Option<Client>) -> Self {
+        // blocking the caller would stall the executor thread, which the send
+        // loop may need to make room in the queue
+        let overflow = match options.queue_overflow {
+            QueueOverflow::Block(_) => QueueOverflow::DropNewest,
+            overflow => overflow,
+        };
+        let queue = Arc::new(Queue::new(options.queue_capacity, overflow));
+        let client_reports = Arc::new(ClientReportRecorder::new());
+        let spool = Spool::from_options(options, client_reports.clone()).map(Arc::new);
+        let client = client.unwrap_or_else(|| {
+            let mut builder = Client::builder().configure_tls(&TlsConfig::from_options(options));
+            if let Some(ref url) = options.http_proxy {
+                builder = builder.proxy(Proxy::http(url.as_ref()).unwrap());
+            };
+            if let Some(ref url) = options.https_proxy {
+                builder = builder.proxy(Proxy::https(url.as_ref()).unwrap());
+            };
+            builder.build().unwrap()
+        });
+
+        let state = SendState::new(options, spool.clone(), client_reports.clone());
+        let shutting_down = state.shutting_down.clone();
+        let worker = TokioWorker {
+            url: options.dsn.as_ref().unwrap().envelope_api_url().to_string(),
+            dsn: options.dsn.clone().unwrap(),
+            user_agent: options.user_agent.to_string(),
+            compression: options.compression,
+            client,
+            state,
+        };
+        handle.spawn(worker.run(queue.clone()));
+
+        TokioHttpTransport {
+            queue,
+            queue_overflows: AtomicU64::new(0),
+            spool,
+            client_reports,
+            shutting_down,
+        }
+    }
+
+    /// Waits until all envelopes queued so far have been sent.
+    ///
+    /// Returns `false` if the timeout elapsed before the queue was drained.
+    pub async fn flush(&self, timeout: Duration) -> bool {
+        let (notify, flushed) = oneshot::channel();
+        let task = Task::Flush(Box::new(move || {
+            notify.send(()).ok();
+        }));
+        if self.queue.force_push(task).is_err() {
+            return true;
+        }
+        match tokio::time::timeout(timeout, flushed).await {
+            Ok(flushed) => flushed.is_ok(),
+            Err(_) => false,
+        }
+    }
+
+    /// Blocks until the send loop has picked up everything queued so far.
+    fn wait_for_queue(&self, timeout: Duration) -> bool {
+        let (notify, flushed) = std_mpsc::channel();
+        let task = Task::Flush(Box::new(move || {
+            notify.send(()).ok();
+        }));
+        if self.queue.force_push(task).is_err() {
+            return true;
+        }
+        flushed.recv_timeout(timeout).is_ok()
+    }
+
+    /// Drains the queue and stops the send loop.
+    ///
+    /// Envelopes sent after the transport was closed are discarded.  Returns
+    /// `false` if the timeout elapsed before the queue was drained.
+    pub async fn close(&self, timeout: Duration) -> bool {
+        // stop retrying so that the queue drains in time
+        self.shutting_down.store(true, Ordering::SeqCst);
+        let flushed = self.flush(timeout).await;
+        self.queue.close();
+        flushed
+    }
+
+    /// Returns the number of envelopes that did not fit into the queue.
+    ///
+    /// Depending on `queue_overflow` these are new or old envelopes that
+    /// were dropped or written to the spool.
+    pub fn queue_overflows(&self) -> u64 {
+        self.queue_overflows.load(Ordering::SeqCst)
+    }
+}
+
+impl Transport for TokioHttpTransport {
+    fn send_event(&self, event: Event<'static>) {
+        self.send_envelope(event.into())
+    }
+
+    fn send_envelope(&self, envelope: Envelope) {
+        let dropped = match self.queue.push(Task::Envelope(envelope)) {
+            Ok(()) => return,
+            Err(PushError::Full(task)) => {
+                self.queue_overflows.fetch_add(1, Ordering::SeqCst);
+                task
+            }
+            Err(PushError::Closed(task)) => task,
+        };
+        match dropped {
+            // keep the envelope around for later if we can
+            Task::Envelope(envelope) => match self.spool {
+                Some(ref spool) => spool.store(&envelope),
+                None => self
+                    .client_reports
+                    .record_envelope(DiscardReason::QueueOverflow, &envelope),
+            },
+            // a pending flush must not get lost when old envelopes are dropped
+            Task::Flush(notify) => {
+                if let Err(PushError::Closed(Task::Flush(notify)))
+                | Err(PushError::Full(Task::Flush(notify))) =
+                    self.queue.force_push(Task::Flush(notify))
+                {
+                    notify();
+                }
+            }
+        }
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        sentry_debug!("flushing tokio transport");
+        self.wait_for_queue(timeout)
+    }
+
+    fn shutdown(&self, timeout: Duration) -> bool {
+        sentry_debug!("shutting down tokio transport");
+        self.shutting_down.store(true, Ordering::SeqCst);
+        self.wait_for_queue(timeout)
+    }
+
+    fn client_reports(&self) -> Option<Arc<ClientReportRecorder>> {
+        Some(self.client_reports.clone())
+    }
+}
+
+impl Drop for TokioHttpTransport {
+    fn drop(&mut self) {
+        // the send loop finishes the queued envelopes and stops
+        self.queue.close();
+    }
+}
+
+struct TokioWorker {
+    url: String,
+    dsn: Dsn,
+    user_agent: String,
+    compression: Compression,
+    client: Client,
+    state: SendState,
+}
+
+impl TokioWorker {
+    async fn run(mut self, queue: Arc<Queue<Task>>) {
+        sentry_debug!("spawning tokio transport");
+        self.replay_spool().await;
+        loop {
+            let task = if self.state.spool.is_some() {
+                match tokio::time::timeout(SPOOL_RETRY_INTERVAL, queue.pop_async()).await {
+                    Ok(Some(task)) => Some(task),
+                    Ok(None) => break,
+                    Err(_) => None,
+                }
+            } else {
+                match queue.pop_async().await {
+                    Some(task) => Some(task),
+                    None => break,
+                }
+            };
+
+            let connected = match task {
+                Some(Task::Envelope(envelope)) => self.send(envelope).await,
+                Some(Task::Flush(notify)) => {
+                    notify();
+                    false
+                }
+                // the queue was idle, try whether sentry is reachable again
+                None => true,
+            };
+            if connected {
+                self.replay_spool().await;
+            }
+        }
+    }
+
+    /// Sends an envelope and returns whether it reached Sentry.
+    ///
+    /// Transient failures are retried according to the retry policy.
+    async fn send(&mut self, envelope: Envelope) -> bool {
+        let envelope = match self.state.filter_envelope(envelope) {
+            Some(envelope) => envelope,
+            None => return false,
+        };
+        let mut retry = 1;
+        loop {
+            match self.post(&envelope).await {
+                SendResult::Sent => return true,
+                SendResult::RateLimited => {
+                    self.state
+                        .client_reports
+                        .record_envelope(DiscardReason::RatelimitBackoff, &envelope);
+                    return false;
+                }
+                SendResult::Retry => {
+                    if let Some(delay) = self.state.retry_delay(retry) {
+                        sentry_debug!("Retrying envelope in {}ms", delay.as_millis());
+                        tokio::time::delay_for(delay).await;
+                        if !self.state.is_shutting_down() {
+                            retry += 1;
+                            continue;
+                        }
+                    }
+                }
+                SendResult::Failed => {}
+            }
+            self.state
+                .spool_or_discard(&envelope, DiscardReason::NetworkError);
+            return false;
+        }
+    }
+
+    /// Sends spooled envelopes oldest first until one fails.
+    async fn replay_spool(&mut self) {
+        let spool = match self.state.spool {
+            Some(ref spool) => spool.clone(),
+            None => return,
+        };
+        while let Some((path, envelope)) = spool.oldest() {
+            let envelope = match self.state.filter_envelope(envelope) {
+                Some(envelope) => envelope,
+                None => {
+                    spool.remove(&path);
+                    continue;
+                }
+            };
+            match self.post(&envelope).await {
+                SendResult::Sent => spool.remove(&path),
+                SendResult::RateLimited | SendResult::Failed | SendResult::Retry => break,
+            }
+        }
+    }
+
+    async fn post(&mut self, envelope: &Envelope) -> SendResult {
+        let (body, encoding) = compress(self.compression, envelope.to_vec());
+        let mut request = self
+            .client
+            .post(self.url.as_str())
+            .header(CONTENT_TYPE, "application/x-sentry-envelope")
+            .header(
+                "X-Sentry-Auth",
+                self.dsn.to_auth(Some(&self.user_agent)).to_string(),
+            );
+        if let Some(encoding) = encoding {
+            request = request.header(CONTENT_ENCODING, encoding);
+        }
+        let result = request.body(body).send().await;
+        match result {
+            Ok(resp) => handle_response(&mut self.state, resp.status(), resp.headers()),
+            Err(err) => {
+                sentry_debug!("Failed to send envelope: {}", err);
+                SendResult::Retry
+            }
+        }
+    }
+}
@@ -1,11 +1,21 @@
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
-use std::sync::{Arc, Condvar, Mutex};
-use std::thread::{self, JoinHandle};
-use std::time::{Duration, SystemTime};
+use std::sync::Arc;
 
 #[cfg(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))]
-use httpdate::parse_http_date;
+use std::{
+    sync::atomic::AtomicU64,
+    sync::{Condvar, Mutex},
+    thread::{self, JoinHandle},
+    time::Instant,
+};
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
 
 #[cfg(feature = "with_curl_transport")]
 use std::io::Cursor;
@@ -13,45 +23,390 @@ use std::io::Cursor;
 use {crate::internals::Scheme, curl, std::io::Read};
 
 #[cfg(feature = "with_reqwest_transport")]
-use reqwest::{blocking::Client, header::RETRY_AFTER, Proxy};
-
+use reqwest::{
+    blocking::Client,
+    header::{CONTENT_ENCODING, CONTENT_TYPE},
+    Proxy,
+};
+#[cfg(any(feature = "with_reqwest_transport", feature = "with_tokio_transport"))]
+use reqwest::{
+    header::{HeaderMap, RETRY_AFTER},
+    StatusCode,
+};
+
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
 use sentry_core::sentry_debug;
 
 use crate::internals::{Transport, TransportFactory};
-use crate::protocol::Event;
+use crate::local_transport::LocalTarget;
+#[cfg(feature = "with_reqwest_transport")]
+use crate::tls::ConfigureTls;
+#[cfg(all(
+    feature = "with_tokio_transport",
+    not(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))
+))]
+use crate::tokio_transport::TokioHttpTransport;
 use crate::ClientOptions;
+#[cfg(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))]
+use {
+    crate::compression::compress,
+    crate::queue::{Pop, PushError, Queue},
+    crate::tls::TlsConfig,
+};
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+use {
+    crate::internals::ClientReportRecorder,
+    crate::protocol::{DiscardReason, Envelope, Event},
+    crate::ratelimit::RateLimiter,
+    crate::spool::Spool,
+    crate::RetryPolicy,
+    rand::random,
+};
+
 /// Creates the default HTTP transport.
 ///
 /// This is the default value for `transport` on the client options.  It
-/// creates a `HttpTransport`.  If no http transport was compiled into the
-/// library it will panic on transport creation.
+/// creates a `HttpTransport`, or a `TokioHttpTransport` on the current tokio
+/// runtime if that is the only transport compiled into the library.  Outside
+/// of a runtime the tokio transport cannot be created and all events are
+/// dropped instead.  If no http transport was compiled into the library it
+/// will panic on transport creation.
+///
+/// The `SENTRY_TRANSPORT` environment variable overrides the HTTP transport
+/// with a local one: `file://<path>` creates a `FileTransport` and `stdout`
+/// a `StdoutTransport`.
 #[derive(Clone)]
 pub struct DefaultTransportFactory;
 
 impl TransportFactory for DefaultTransportFactory {
     fn create_transport(&self, options: &ClientOptions) -> Arc<dyn Transport> {
+        if let Some(target) = LocalTarget::from_env() {
+            return target.create_transport();
+        }
         #[cfg(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))]
         {
             Arc::new(HttpTransport::new(options))
         }
-        #[cfg(not(any(feature = "with_reqwest_transport", feature = "with_curl_transport")))]
+        #[cfg(all(
+            feature = "with_tokio_transport",
+            not(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))
+        ))]
+        {
+            match TokioHttpTransport::try_new(options) {
+                Ok(transport) => Arc::new(transport),
+                Err(err) => {
+                    sentry_debug!("Failed to create the tokio transport: {}", err);
+                    Arc::new(DisabledTransport)
+                }
+            }
+        }
+        #[cfg(not(any(
+            feature = "with_reqwest_transport",
+            feature = "with_curl_transport",
+            feature = "with_tokio_transport"
+        )))]
         {
+            let _options = options;
             panic!("sentry crate was compiled without transport")
         }
     }
 }
 
+/// A transport dropping all events.
+///
+/// This stands in for the tokio transport outside of a runtime.
+#[cfg(all(
+    feature = "with_tokio_transport",
+    not(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))
+))]
+struct DisabledTransport;
+
+#[cfg(all(
+    feature = "with_tokio_transport",
+    not(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))
+))]
+impl Transport for DisabledTransport {
+    fn send_event(&self, event: Event<'static>) {
+        let _event = event;
+    }
+}
+
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+/// The interval in which spooled envelopes are retried while idle.
+pub(crate) const SPOOL_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+/// The result of an attempt to send an envelope.
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+pub(crate) enum SendResult {
+    /// The envelope reached Sentry, whether it was accepted or not.
+    Sent,
+    /// Sentry rejected the envelope because of rate limits.
+    RateLimited,
+    /// The envelope could not be delivered and may be retried later.
+    Failed,
+    /// The envelope could not be delivered because of a transient failure
+    /// and may be retried right away.
+    Retry,
+}
+
+/// The state an HTTP transport keeps across the envelopes it sends.
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+pub(crate) struct SendState {
+    pub spool: Option<Arc<Spool>>,
+    pub rate_limiter: RateLimiter,
+    pub client_reports: Arc<ClientReportRecorder>,
+    pub retry_policy: RetryPolicy,
+    pub shutting_down: Arc<AtomicBool>,
+}
+
+#[cfg(any(
+    feature = "with_reqwest_transport",
+    feature = "with_curl_transport",
+    feature = "with_tokio_transport"
+))]
+impl SendState {
+    /// Creates the state for a new transport.
+    pub fn new(
+        options: &ClientOptions,
+        spool: Option<Arc<Spool>>,
+        client_reports: Arc<ClientReportRecorder>,
+    ) -> Self {
+        SendState {
+            spool,
+            rate_limiter: RateLimiter::new(),
+            client_reports,
+            retry_policy: options.retry_policy.clone(),
+            shutting_down: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    /// Returns whether the transport is shutting down.
+    pub fn is_shutting_down(&self) -> bool {
+        self.shutting_down.load(Ordering::SeqCst)
+    }
+
+    /// Classifies the status code of a response.
+    pub fn status_result(&self, status_code: u16) -> SendResult {
+        if (200..300).contains(&status_code) {
+            return SendResult::Sent;
+        }
+        sentry_debug!("Failed to send envelope: {}", status_code);
+        if status_code == 429 {
+            SendResult::RateLimited
+        } else if self.retry_policy.is_retryable(status_code) {
+            SendResult::Retry
+        } else if status_code >= 500 {
+            SendResult::Failed
+        } else {
+            SendResult::Sent
+        }
+    }
+
+    /// Returns the delay before the given retry of an envelope, or `None`
+    /// if it is not retried anymore.
+    pub fn retry_delay(&self, retry: u32) -> Option<Duration> {
+        if retry >= self.retry_policy.max_attempts || self.is_shutting_down() {
+            return None;
+        }
+        // `f32::clamp` is not available on all supported compilers
+        #[allow(clippy::manual_clamp)]
+        let jitter = self.retry_policy.jitter.max(0.0).min(1.0) * random::<f32>();
+        Some(self.retry_policy.delay(retry).mul_f32(1.0 - jitter))
+    }
+
+    /// Applies the rate limits to the envelope and records the dropped items.
+    pub fn filter_envelope(&self, envelope: Envelope) -> Option<Envelope> {
+        let mut dropped: Vec<_> = envelope.items().map(|item| item.data_category()).collect();
+        let envelope = self.rate_limiter.filter_envelope(envelope);
+        for item in envelope.iter().flat_map(|envelope| envelope.items()) {
+            if let Some(pos) = dropped.iter().position(|c| *c == item.data_category()) {
+                dropped.swap_remove(pos);
+            }
+        }
+        for category in dropped {
+            self.client_reports
+                .record(DiscardReason::RatelimitBackoff, category, 1);
+        }
+        envelope
+    }
+
+    /// Keeps an envelope that could not be delivered in the spool, or
+    /// records it as discarded if there is none.
+    pub fn spool_or_discard(&self, envelope: &Envelope, reason: DiscardReason) {
+        match self.spool {
+            Some(ref spool) => spool.store(envelope),
+            None => self.client_reports.record_envelope(reason, envelope),
+        }
+    }
+}
+
+/// The background worker shared by the blocking HTTP transports.
+///
+/// It takes envelopes off the queue and hands them to the transport
+/// specific send function.  Envelopes that cannot be delivered are written
+/// to the spool if one is configured, and replayed once an envelope was
+/// sent successfully or the queue has been idle for a while.
+#[cfg(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))]
+struct TransportWorker {
+    queue: Arc<Queue<Envelope>>,
+    signal: Arc<Condvar>,
+    shutdown_immediately: Arc<AtomicBool>,
+    queue_size: Arc<Mutex<usize>>,
+    state: SendState,
+}
+
 #[cfg(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))]
-fn parse_retry_after(s: &str) -> Option<SystemTime> {
-    if let Ok(value) = s.parse::<f64>() {
-        Some(SystemTime::now() + Duration::from_secs(value.ceil() as u64))
-    } else if let Ok(value) = parse_http_date(s) {
-        Some(value)
-    } else {
-        None
+impl TransportWorker {
+    fn run<F: FnMut(&Envelope, &mut SendState) -> SendResult>(mut self, mut send: F) {
+        self.replay_spool(&mut send);
+        loop {
+            let timeout = self.state.spool.as_ref().map(|_| SPOOL_RETRY_INTERVAL);
+            let envelope = match self.queue.pop(timeout) {
+                Pop::Item(envelope) => Some(envelope),
+                Pop::Timeout => None,
+                Pop::Closed => break,
+            };
+
+            // on drop we want to not continue processing the queue.
+            if self.shutdown_immediately.load(Ordering::SeqCst) {
+                if let Some(ref spool) = self.state.spool {
+                    for envelope in envelope.into_iter().chain(self.queue.drain()) {
+                        spool.store(&envelope);
+                    }
+                }
+                let mut size = self.queue_size.lock().unwrap();
+                *size = 0;
+                self.signal.notify_all();
+                break;
+            }
+
+            let connected = match envelope {
+                Some(envelope) => {
+                    let sent = self.send(envelope, &mut send);
+                    let mut size = self.queue_size.lock().unwrap();
+                    *size -= 1;
+                    if *size == 0 {
+                        self.signal.notify_all();
+                    }
+                    sent
+                }
+                // the queue was idle, try whether sentry is reachable again
+                None => true,
+            };
+            if connected {
+                self.replay_spool(&mut send);
+            }
+        }
+    }
+
+    /// Sends an envelope and returns whether it reached Sentry.
+    ///
+    /// Transient failures are retried according to the retry policy.
+    fn send<F: FnMut(&Envelope, &mut SendState) -> SendResult>(
+        &mut self,
+        envelope: Envelope,
+        send: &mut F,
+    ) -> bool {
+        let envelope = match self.state.filter_envelope(envelope) {
+            Some(envelope) => envelope,
+            None => return false,
+        };
+        let mut retry = 1;
+        loop {
+            match send(&envelope, &mut self.state) {
+                SendResult::Sent => return true,
+                SendResult::RateLimited => {
+                    self.state
+                        .client_reports
+                        .record_envelope(DiscardReason::RatelimitBackoff, &envelope);
+                    return false;
+                }
+                SendResult::Retry => {
+                    if let Some(delay) = self.state.retry_delay(retry) {
+                        sentry_debug!("Retrying envelope in {}ms", delay.as_millis());
+                        if self.wait_for_retry(delay) {
+                            retry += 1;
+                            continue;
+                        }
+                    }
+                }
+                SendResult::Failed => {}
+            }
+            self.state
+                .spool_or_discard(&envelope, DiscardReason::NetworkError);
+            return false;
+        }
+    }
+
+    /// Waits before retrying an envelope.
+    ///
+    /// Returns `false` if the transport started shutting down in the meantime.
+    fn wait_for_retry(&self, delay: Duration) -> bool {
+        let deadline = Instant::now().checked_add(delay);
+        let mut guard = self.queue_size.lock().unwrap();
+        while !self.state.is_shutting_down() {
+            guard = match deadline {
+                Some(deadline) => {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return true;
+                    }
+                    self.signal.wait_timeout(guard, deadline - now).unwrap().0
+                }
+                None => self.signal.wait(guard).unwrap(),
+            };
+        }
+        false
+    }
+
+    /// Sends spooled envelopes oldest first until one fails.
+    ///
+    /// Items that are rate limited in the meantime are discarded.
+    fn replay_spool<F: FnMut(&Envelope, &mut SendState) -> SendResult>(&mut self, send: &mut F) {
+        let spool = match self.state.spool {
+            Some(ref spool) => spool.clone(),
+            None => return,
+        };
+        while let Some((path, envelope)) = spool.oldest() {
+            if self.shutdown_immediately.load(Ordering::SeqCst) {
+                break;
+            }
+            let envelope = match self.state.filter_envelope(envelope) {
+                Some(envelope) => envelope,
+                None => {
+                    spool.remove(&path);
+                    continue;
+                }
+            };
+            match send(&envelope, &mut self.state) {
+                SendResult::Sent => spool.remove(&path),
+                SendResult::RateLimited | SendResult::Failed | SendResult::Retry => break,
+            }
+        }
     }
 }
 
+#[cfg(any(feature = "with_reqwest_transport", feature = "with_curl_transport"))]
 macro_rules! implement_http_transport {
     (
         $(#[$attr:meta])*
@@ -61,9 +416,13 @@ macro_rules! implement_http_transport {
     ) => {
         $(#[$attr])*
         pub struct $typename {
-            sender: Mutex<SyncSender<Option<Event<'static>>>>,
+            queue: Arc<Queue<Envelope>>,
+            queue_overflows: AtomicU64,
+            spool: Option<Arc<Spool>>,
+            client_reports: Arc<ClientReportRecorder>,
             shutdown_signal: Arc<Condvar>,
             shutdown_immediately: Arc<AtomicBool>,
+            shutting_down: Arc<AtomicBool>,
             queue_size: Arc<Mutex<usize>>,
             _handle: Option<JoinHandle<()>>,
         }
@@ -85,62 +444,114 @@ macro_rules! implement_http_transport {
 
                 fn http_client($hc_options: &ClientOptions, $hc_client: Option<$hc_client_ty>) -> $hc_ret { $hc_body }
 
-                let (sender, receiver) = sync_channel(30);
+                let queue = Arc::new(Queue::new(options.queue_capacity, options.queue_overflow));
                 let shutdown_signal = Arc::new(Condvar::new());
                 let shutdown_immediately = Arc::new(AtomicBool::new(false));
                 #[allow(clippy::mutex_atomic)]
                 let queue_size = Arc::new(Mutex::new(0));
+                let client_reports = Arc::new(ClientReportRecorder::new());
+                let spool = Spool::from_options(options, client_reports.clone()).map(Arc::new);
                 let http_client = http_client(options, $hc_client);
-                let _handle = Some(spawn(
-                    options,
-                    receiver,
-                    shutdown_signal.clone(),
-                    shutdown_immediately.clone(),
-                    queue_size.clone(),
-                    http_client,
-                ));
+                let state = SendState::new(options, spool.clone(), client_reports.clone());
+                let shutting_down = state.shutting_down.clone();
+                let worker = TransportWorker {
+                    queue: queue.clone(),
+                    signal: shutdown_signal.clone(),
+                    shutdown_immediately: shutdown_immediately.clone(),
+                    queue_size: queue_size.clone(),
+                    state,
+                };
+                let _handle = Some(spawn(options, worker, http_client));
                 $typename {
-                    sender: Mutex::new(sender),
+                    queue,
+                    queue_overflows: AtomicU64::new(0),
+                    spool,
+                    client_reports,
                     shutdown_signal,
                     shutdown_immediately,
+                    shutting_down,
                     queue_size,
                     _handle,
                 }
             }
+
+            /// Returns the number of envelopes that did not fit into the queue.
+            ///
+            /// Depending on `queue_overflow` these are new or old envelopes
+            /// that were dropped or written to the spool.
+            pub fn queue_overflows(&self) -> u64 {
+                self.queue_overflows.load(Ordering::SeqCst)
+            }
         }
 
         impl Transport for $typename {
             fn send_event(&self, event: Event<'static>) {
+                self.send_envelope(event.into())
+            }
+
+            fn send_envelope(&self, envelope: Envelope) {
                 // we count up before we put the item on the queue and in case the
                 // queue is filled with too many items or we shut down, we decrement
                 // the count again as there is nobody that can pick it up.
                 *self.queue_size.lock().unwrap() += 1;
-                if self.sender.lock().unwrap().try_send(Some(event)).is_err() {
-                    *self.queue_size.lock().unwrap() -= 1;
+                let dropped = match self.queue.push(envelope) {
+                    Ok(()) => return,
+                    Err(PushError::Full(envelope)) => {
+                        self.queue_overflows.fetch_add(1, Ordering::SeqCst);
+                        envelope
+                    }
+                    Err(PushError::Closed(envelope)) => envelope,
+                };
+                *self.queue_size.lock().unwrap() -= 1;
+                // keep the envelope around for later if we can
+                match self.spool {
+                    Some(ref spool) => spool.store(&dropped),
+                    None => self
+                        .client_reports
+                        .record_envelope(DiscardReason::QueueOverflow, &dropped),
+                }
+            }
+
+            fn flush(&self, timeout: Duration) -> bool {
+                sentry_debug!("flushing http transport");
+                let deadline = Instant::now() + timeout;
+                let mut guard = self.queue_size.lock().unwrap();
+                while *guard > 0 {
+                    let now = Instant::now();
+                    if now >= deadline {
+                        return false;
+                    }
+                    guard = self.shutdown_signal.wait_timeout(guard, deadline - now).unwrap().0;
                 }
+                true
             }
 
             fn shutdown(&self, timeout: Duration) -> bool {
                 sentry_debug!("shutting down http transport");
                 let guard = self.queue_size.lock().unwrap();
+                // stop retrying so that the queue drains in time
+                self.shutting_down.store(true, Ordering::SeqCst);
+                self.shutdown_signal.notify_all();
                 if *guard == 0 {
                     true
                 } else {
-                    if let Ok(sender) = self.sender.lock() {
-                        sender.send(None).ok();
-                    }
+                    self.queue.close();
                     self.shutdown_signal.wait_timeout(guard, timeout).is_ok()
                 }
             }
+
+            fn client_reports(&self) -> Option<Arc<ClientReportRecorder>> {
+                Some(self.client_reports.clone())
+            }
         }
 
         impl Drop for $typename {
             fn drop(&mut self) {
                 sentry_debug!("dropping http transport");
                 self.shutdown_immediately.store(true, Ordering::SeqCst);
-                if let Ok(sender) = self.sender.lock() {
-                    sender.send(None).ok();
-                }
+                self.shutting_down.store(true, Ordering::SeqCst);
+                self.shutdown_signal.notify_all();
+                self.queue.close();
             }
         }
     }
@@ -148,7 +559,7 @@ macro_rules! implement_http_transport {
 
 #[cfg(feature = "with_reqwest_transport")]
 implement_http_transport! {
-    /// A transport can send events via HTTP to sentry via `reqwest`.
+    /// A transport can send envelopes via HTTP to sentry via `reqwest`.
     ///
     /// When the `with_default_transport` feature is enabled this will currently
     /// be the default transport.  This is separately enabled by the
@@ -157,25 +568,22 @@ implement_http_transport! {
 
     fn spawn(
         options: &ClientOptions,
-        receiver: Receiver<Option<Event<'static>>>,
-        signal: Arc<Condvar>,
-        shutdown_immediately: Arc<AtomicBool>,
-        queue_size: Arc<Mutex<usize>>,
+        worker: TransportWorker,
         http_client: Option<Client>,
     ) {
         let dsn = options.dsn.clone().unwrap();
         let user_agent = options.user_agent.to_string();
-
-        let mut disabled = None::<SystemTime>;
         let http_proxy = options.http_proxy.as_ref().map(ToString::to_string);
         let https_proxy = options.https_proxy.as_ref().map(ToString::to_string);
+        let compression = options.compression;
+        let tls = TlsConfig::from_options(options);
 
         thread::Builder::new()
             .name("sentry-transport".to_string())
             .spawn(move || {
                 sentry_debug!("spawning reqwest transport");
                 let http_client = http_client.unwrap_or_else(|| {
-                    let mut builder = Client::builder();
+                    let mut builder = Client::builder().configure_tls(&tls);
                     if let Some(url) = http_proxy {
                         builder = builder.proxy(Proxy::http(&url).unwrap());
                     };
@@ -185,59 +593,25 @@ implement_http_transport! {
                     builder.build().unwrap()
                 });
 
-                let url = dsn.store_api_url().to_string();
-
-                while let Some(event) = receiver.recv().unwrap_or(None) {
-                    // on drop we want to not continue processing the queue.
-                    if shutdown_immediately.load(Ordering::SeqCst) {
-                        let mut size = queue_size.lock().unwrap();
-                        *size = 0;
-                        signal.notify_all();
-                        break;
-                    }
-
-                    // while we are disabled due to rate limits, skip
-                    if let Some(ts) = disabled {
-                        if let Ok(time_left) = ts.duration_since(SystemTime::now()) {
-                            sentry_debug!(
-                                "Skipping event send because we're disabled due to rate limits for {}s",
-                                time_left.as_secs()
-                            );
-                            continue;
-                        } else {
-                            disabled = None;
-                        }
-                    }
+                let url = dsn.envelope_api_url().to_string();
 
-                    match http_client
+                worker.run(move |envelope, state| {
+                    let (body, encoding) = compress(compression, envelope.to_vec());
+                    let mut request = http_client
                         .post(url.as_str())
-                        .json(&event)
-                        .header("X-Sentry-Auth", dsn.to_auth(Some(&user_agent)).to_string())
-                        .send()
-                    {
-                        Ok(resp) => {
-                            if resp.status() == 429 {
-                                if let Some(retry_after) = resp
-                                    .headers()
-                                    .get(RETRY_AFTER)
-                                    .and_then(|x| x.to_str().ok())
-                                    .and_then(parse_retry_after)
-                                {
-                                    disabled = Some(retry_after);
-                                }
-                            }
-                        }
+                        .header(CONTENT_TYPE, "application/x-sentry-envelope")
+                        .header("X-Sentry-Auth", dsn.to_auth(Some(&user_agent)).to_string());
+                    if let Some(encoding) = encoding {
+                        request = request.header(CONTENT_ENCODING, encoding);
+                    }
+                    match request.body(body).send() {
+                        Ok(resp) => handle_response(state, resp.status(), resp.headers()),
                         Err(err) => {
-                            sentry_debug!("Failed to send event: {}", err);
+                            sentry_debug!("Failed to send envelope: {}", err);
+                            SendResult::Retry
                         }
                     }
-
-                    let mut size = queue_size.lock().unwrap();
-                    *size -= 1;
-                    if *size == 0 {
-                        signal.notify_all();
-                    }
-                }
+                })
             }).unwrap()
     }
 
@@ -246,58 +620,57 @@ implement_http_transport! {
     }
 }
 
+/// Updates the rate limits from a response and classifies the result.
+#[cfg(any(feature = "with_reqwest_transport", feature = "with_tokio_transport"))]
+pub(crate) fn handle_response(
+    state: &mut SendState,
+    status: StatusCode,
+    headers: &HeaderMap,
+) -> SendResult {
+    let header = |name| headers.get(name).and_then(|x| x.to_str().ok());
+    if let Some(rate_limits) = header("x-sentry-rate-limits") {
+        state.rate_limiter.update_from_sentry_header(rate_limits);
+    } else if status == StatusCode::TOO_MANY_REQUESTS {
+        match header(RETRY_AFTER.as_str()) {
+            Some(retry_after) => state.rate_limiter.update_from_retry_after(retry_after),
+            None => state.rate_limiter.update_from_429(),
+        }
+    }
+    state.status_result(status.as_u16())
+}
+
 #[cfg(feature = "with_curl_transport")]
 implement_http_transport! {
-    /// A transport can send events via HTTP to sentry via `curl`.
+    /// A transport can send envelopes via HTTP to sentry via `curl`.
     ///
     /// This is enabled by the `with_curl_transport` flag.
     pub struct CurlHttpTransport;
 
     fn spawn(
         options: &ClientOptions,
-        receiver: Receiver<Option<Event<'static>>>,
-        signal: Arc<Condvar>,
-        shutdown_immediately: Arc<AtomicBool>,
-        queue_size: Arc<Mutex<usize>>,
+        worker: TransportWorker,
         http_client: curl::easy::Easy,
     ) {
         let dsn = options.dsn.clone().unwrap();
         let user_agent = options.user_agent.to_string();
         let http_proxy = options.http_proxy.as_ref().map(ToString::to_string);
         let https_proxy = options.https_proxy.as_ref().map(ToString::to_string);
+        let compression = options.compression;
+        let tls = TlsConfig::from_options(options);
 
-        let mut disabled = None::<SystemTime>;
         let mut handle = http_client;
 
         thread::spawn(move || {
             sentry_debug!("spawning curl transport");
-            let url = dsn.store_api_url().to_string();
-
-            while let Some(event) = receiver.recv().unwrap_or(None) {
-                // on drop we want to not continue processing the queue.
-                if shutdown_immediately.load(Ordering::SeqCst) {
-                    let mut size = queue_size.lock().unwrap();
-                    *size = 0;
-                    signal.notify_all();
-                    break;
-                }
-
-                // while we are disabled due to rate limits, skip
-                if let Some(ts) = disabled {
-                    if let Ok(time_left) = ts.duration_since(SystemTime::now()) {
-                        sentry_debug!(
-                            "Skipping event send because we're disabled due to rate limits for {}s",
-                            time_left.as_secs()
-                        );
-                        continue;
-                    } else {
-                        disabled = None;
-                    }
-                }
+            let url = dsn.envelope_api_url().to_string();
 
+            worker.run(move |envelope, state| {
                 handle.reset();
                 handle.url(&url).unwrap();
                 handle.custom_request("POST").unwrap();
+                if let Err(err) = tls.apply_to_curl(&mut handle) {
+                    sentry_debug!("Failed to configure TLS: {}", err);
+                }
 
                 match (dsn.scheme(), &http_proxy, &https_proxy) {
                     (Scheme::Https, _, &Some(ref proxy)) => {
@@ -309,12 +682,17 @@ implement_http_transport! {
                     _ => {}
                 }
 
-                let mut body = Cursor::new(serde_json::to_vec(&event).unwrap());
+                let (body, encoding) = compress(compression, envelope.to_vec());
+                let mut body = Cursor::new(body);
                 let mut retry_after = None;
+                let mut rate_limits = None;
                 let mut headers = curl::easy::List::new();
                 headers.append(&format!("X-Sentry-Auth: {}", dsn.to_auth(Some(&user_agent)))).unwrap();
                 headers.append("Expect:").unwrap();
-                headers.append("Content-Type: application/json").unwrap();
+                headers.append("Content-Type: application/x-sentry-envelope").unwrap();
+                if let Some(encoding) = encoding {
+                    headers.append(&format!("Content-Encoding: {}", encoding)).unwrap();
+                }
                 handle.http_headers(headers).unwrap();
                 handle.upload(true).unwrap();
                 handle.in_filesize(body.get_ref().len() as u64).unwrap();
@@ -330,44 +708,50 @@ implement_http_transport! {
                     sentry_debug!("curl: {}{}", prefix, String::from_utf8_lossy(data).trim());
                 }).unwrap();
 
-                {
+                let result = {
                     let mut handle = handle.transfer();
                     let retry_after_setter = &mut retry_after;
+                    let rate_limits_setter = &mut rate_limits;
                     handle.header_function(move |data| {
                         if let Ok(data) = std::str::from_utf8(data) {
-                            let mut iter = data.split(':');
+                            let mut iter = data.splitn(2, ':');
                             if let Some(key) = iter.next().map(str::to_lowercase) {
+                                let value = iter.next().map(|x| x.trim().to_string());
                                 if key == "retry-after" {
-                                    *retry_after_setter = iter.next().map(|x| x.trim().to_string());
+                                    *retry_after_setter = value;
+                                } else if key == "x-sentry-rate-limits" {
+                                    *rate_limits_setter = value;
                                 }
                             }
                         }
                         true
                     }).unwrap();
-                    handle.perform().ok();
+                    handle.perform()
+                };
+
+                if let Err(err) = result {
+                    sentry_debug!("Failed to send envelope: {}", err);
+                    return SendResult::Retry;
                 }
 
-                match handle.response_code() {
-                    Ok(429) => {
-                        if let Some(retry_after) = retry_after
-                            .as_deref()
-                            .and_then(parse_retry_after)
-                        {
-                            disabled = Some(retry_after);
-                        }
-                    }
-                    Ok(200) | Ok(201) => {}
-                    _ => {
-                        sentry_debug!("Failed to send event");
+                let response_code = handle.response_code();
+                if let Some(rate_limits) = rate_limits {
+                    state.rate_limiter.update_from_sentry_header(&rate_limits);
+                } else if let Ok(429) = response_code {
+                    match retry_after {
+                        Some(retry_after) => state.rate_limiter.update_from_retry_after(&retry_after),
+                        None => state.rate_limiter.update_from_429(),
                     }
                 }
 
-                let mut size = queue_size.lock().unwrap();
-                *size -= 1;
-                if *size == 0 {
-                    signal.notify_all();
+                match response_code {
+                    Ok(code) => state.status_result(code as u16),
+                    Err(_) => {
+                        sentry_debug!("Failed to send envelope");
+                        SendResult::Sent
+                    }
                 }
-            }
+            })
         })
     }
 
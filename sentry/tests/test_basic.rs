@@ -76,6 +76,53 @@ fn test_breadcrumbs() {
     );
 }
 
+#[test]
+fn test_scope_snapshot() {
+    sentry::test::with_captured_events(|| {
+        sentry::configure_scope(|scope| {
+            scope.set_tag("tenant", "acme");
+            scope.set_extra("attempt", 1.into());
+            scope.set_transaction(Some("/users"));
+            scope.set_user(Some(sentry::User {
+                id: Some("42".into()),
+                ..Default::default()
+            }));
+        });
+        sentry::add_breadcrumb(sentry::Breadcrumb {
+            message: Some("First breadcrumb".into()),
+            ..Default::default()
+        });
+
+        let snapshot = sentry::Hub::current().scope_snapshot();
+        sentry::configure_scope(|scope| {
+            scope.set_tag("tenant", "other");
+            scope.set_level(Some(sentry::Level::Warning));
+        });
+
+        assert_eq!(
+            snapshot.tags().collect::<Vec<_>>(),
+            vec![("tenant", "acme")]
+        );
+        assert_eq!(
+            snapshot.extra().collect::<Vec<_>>(),
+            vec![("attempt", &1.into())]
+        );
+        assert_eq!(snapshot.contexts().count(), 0);
+        assert_eq!(snapshot.transaction(), Some("/users"));
+        assert_eq!(snapshot.user().unwrap().id.as_deref(), Some("42"));
+        assert_eq!(snapshot.level(), None);
+        let breadcrumb = snapshot.breadcrumbs().next_back().unwrap();
+        assert_eq!(breadcrumb.message.as_deref(), Some("First breadcrumb"));
+
+        let current = sentry::Hub::current().scope_snapshot();
+        assert_eq!(
+            current.tags().collect::<Vec<_>>(),
+            vec![("tenant", "other")]
+        );
+        assert_eq!(current.level(), Some(sentry::Level::Warning));
+    });
+}
+
 #[test]
 fn test_factory() {
     struct TestTransport(Arc<AtomicUsize>);
@@ -113,3 +160,58 @@ fn test_factory() {
 
     assert_eq!(events.load(Ordering::SeqCst), 1);
 }
+
+#[test]
+fn test_captured_envelopes() {
+    let envelopes = sentry::test::with_captured_envelopes(|| {
+        sentry::capture_message("Hello World!", sentry::Level::Warning);
+    });
+    assert_eq!(envelopes.len(), 1);
+
+    let envelope = &envelopes[0];
+    let event = envelope.event().unwrap();
+    assert_eq!(event.message.as_ref().unwrap(), "Hello World!");
+    assert_eq!(envelope.uuid(), Some(&event.event_id));
+    assert_eq!(envelope.items().count(), 1);
+}
+
+#[test]
+fn test_attachments() {
+    let path = std::env::temp_dir().join("sentry-test-attachments.log");
+    std::fs::write(&path, "log tail").unwrap();
+
+    let envelopes = sentry::test::with_captured_envelopes(|| {
+        sentry::configure_scope(|scope| {
+            scope.add_attachment(sentry::protocol::Attachment {
+                content_type: Some("application/json".into()),
+                ..sentry::protocol::Attachment::from_bytes("config.json", "{}")
+            });
+            scope.add_attachment(sentry::protocol::Attachment::from_path(&path));
+        });
+        sentry::capture_message("with attachments", sentry::Level::Error);
+
+        sentry::configure_scope(|scope| scope.clear_attachments());
+        sentry::capture_message("without attachments", sentry::Level::Error);
+    });
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(envelopes.len(), 2);
+
+    let attachments: Vec<_> = envelopes[0]
+        .items()
+        .filter_map(|item| match item {
+            sentry::protocol::EnvelopeItem::Attachment(attachment) => Some(attachment),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(attachments.len(), 2);
+    assert_eq!(attachments[0].filename, "config.json");
+    assert_eq!(
+        attachments[0].content_type.as_deref(),
+        Some("application/json")
+    );
+    assert_eq!(attachments[0].bytes().unwrap(), b"{}");
+    assert_eq!(attachments[1].filename, "sentry-test-attachments.log");
+    assert_eq!(attachments[1].bytes().unwrap(), b"log tail");
+
+    assert_eq!(envelopes[1].items().count(), 1);
+}
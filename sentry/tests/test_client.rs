@@ -3,6 +3,8 @@
 use std::panic;
 use std::sync::Arc;
 
+use sentry::protocol::{DataCategory, DiscardReason, EnvelopeItem, Event, Exception};
+
 #[test]
 fn test_into_client() {
     let c: sentry::Client = sentry::Client::from_config("https://public@example.com/42");
@@ -56,3 +58,188 @@ fn test_unwind_safe() {
 
     assert_eq!(events.len(), 1);
 }
+
+#[test]
+fn test_client_reports() {
+    let transport = sentry::test::TestTransport::new();
+    let options = sentry::ClientOptions {
+        dsn: Some("https://public@example.com/1".parse().unwrap()),
+        transport: Some(Arc::new(transport.clone())),
+        before_send: Some(Arc::new(|event| match event.message.as_deref() {
+            Some("drop me") => None,
+            _ => Some(event),
+        })),
+        ..sentry::ClientOptions::default()
+    };
+    let client: Arc<sentry::Client> = Arc::new(options.into());
+
+    for message in &["drop me", "drop me", "keep me"] {
+        let event = Event {
+            message: Some(message.to_string()),
+            ..Default::default()
+        };
+        client.capture_event(event, None);
+    }
+
+    let discarded = client.discarded_events();
+    assert_eq!(discarded.len(), 1);
+    assert_eq!(discarded[0].reason, DiscardReason::BeforeSend);
+    assert_eq!(discarded[0].category, DataCategory::Error);
+    assert_eq!(discarded[0].quantity, 2);
+
+    // the pending counts are sent when the client closes
+    client.close(None);
+    let reports: Vec<_> = transport
+        .fetch_and_clear_envelopes()
+        .into_iter()
+        .flat_map(|envelope| envelope.into_items())
+        .filter_map(|item| match item {
+            EnvelopeItem::ClientReport(report) => Some(report),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(reports.len(), 1);
+    assert_eq!(reports[0].discarded_events, discarded);
+    assert_eq!(client.discarded_events(), discarded);
+}
+
+#[test]
+fn test_flush() {
+    let transport = sentry::test::TestTransport::new();
+    let options = sentry::ClientOptions {
+        dsn: Some("https://public@example.com/1".parse().unwrap()),
+        transport: Some(Arc::new(transport.clone())),
+        before_send: Some(Arc::new(|_| None)),
+        ..sentry::ClientOptions::default()
+    };
+    let client: Arc<sentry::Client> = Arc::new(options.into());
+    let hub = Arc::new(sentry::Hub::new(Some(client.clone()), Default::default()));
+
+    // flushing sends the pending client reports but keeps the client usable
+    sentry::Hub::run(hub, || {
+        sentry::capture_message("dropped", sentry::Level::Error);
+        assert!(sentry::flush(None));
+    });
+    let envelopes = transport.fetch_and_clear_envelopes();
+    assert_eq!(envelopes.len(), 1);
+    assert!(client.is_enabled());
+
+    client.capture_event(Event::default(), None);
+    assert!(client.flush(Some(std::time::Duration::from_secs(1))));
+    assert_eq!(transport.fetch_and_clear_envelopes().len(), 1);
+    assert_eq!(client.discarded_events()[0].quantity, 2);
+}
+
+#[test]
+fn test_client_reports_shared_by_clones() {
+    let transport = sentry::test::TestTransport::new();
+    let client = sentry::Client::from(sentry::ClientOptions {
+        dsn: Some("https://public@example.com/1".parse().unwrap()),
+        transport: Some(Arc::new(transport.clone())),
+        sample_rate: 0.0,
+        ..sentry::ClientOptions::default()
+    });
+    client.capture_event(Event::default(), None);
+
+    // the report is only sent once the last clone is gone
+    drop(client.clone());
+    assert!(transport.fetch_and_clear_envelopes().is_empty());
+    client.close(None);
+    assert_eq!(transport.fetch_and_clear_envelopes().len(), 1);
+}
+
+#[test]
+fn test_ignore_errors() {
+    let transport = sentry::test::TestTransport::new();
+    let options = sentry::ClientOptions {
+        dsn: Some("https://public@example.com/1".parse().unwrap()),
+        transport: Some(Arc::new(transport.clone())),
+        ignore_errors: vec![
+            "Broken pipe".into(),
+            sentry::IgnorePattern::regex("^Client(Disconnect|Abort)$").unwrap(),
+        ],
+        ..sentry::ClientOptions::default()
+    };
+    let client: Arc<sentry::Client> = Arc::new(options.into());
+
+    let error = |ty: &str, value: &str| Event {
+        exception: vec![Exception {
+            ty: ty.into(),
+            value: Some(value.into()),
+            ..Default::default()
+        }]
+        .into(),
+        ..Default::default()
+    };
+    client.capture_event(error("IoError", "Broken pipe (os error 32)"), None);
+    client.capture_event(error("ClientDisconnect", "gone"), None);
+    client.capture_event(error("ClientDisconnected", "gone"), None);
+    client.capture_event(
+        Event {
+            message: Some("write failed: Broken pipe".into()),
+            ..Default::default()
+        },
+        None,
+    );
+
+    let events = transport.fetch_and_clear_events();
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].exception[0].ty, "ClientDisconnected");
+
+    let discarded = client.discarded_events();
+    assert_eq!(discarded.len(), 1);
+    assert_eq!(discarded[0].reason, DiscardReason::Filtered);
+    assert_eq!(discarded[0].category, DataCategory::Error);
+    assert_eq!(discarded[0].quantity, 3);
+}
+
+#[test]
+fn test_sampler() {
+    let transport = sentry::test::TestTransport::new();
+    let options = sentry::ClientOptions {
+        dsn: Some("https://public@example.com/1".parse().unwrap()),
+        transport: Some(Arc::new(transport.clone())),
+        // the sampler takes precedence over the sample rate
+        sample_rate: 0.0,
+        sampler: Some(Arc::new(|event| {
+            let is_chatty =
+                event.level == sentry::Level::Warning && event.logger.as_deref() == Some("chatty");
+            let is_free = event.tags.get("tenant").map(String::as_str) == Some("free");
+            if is_chatty || is_free {
+                0.0
+            } else {
+                1.0
+            }
+        })),
+        ..sentry::ClientOptions::default()
+    };
+    let client = Arc::new(sentry::Client::from(options));
+    let hub = Arc::new(sentry::Hub::new(Some(client.clone()), Default::default()));
+
+    sentry::Hub::run(hub, || {
+        let warning = |logger: &str| Event {
+            level: sentry::Level::Warning,
+            logger: Some(logger.into()),
+            ..Default::default()
+        };
+        sentry::capture_event(warning("chatty"));
+        sentry::capture_event(warning("quiet"));
+        sentry::with_scope(
+            |scope| scope.set_tag("tenant", "free"),
+            || sentry::capture_event(warning("quiet")),
+        );
+    });
+
+    let envelopes = transport.fetch_and_clear_envelopes();
+    assert_eq!(envelopes.len(), 1);
+    assert_eq!(envelopes[0].headers().sample_rate, Some(1.0));
+    let event = envelopes[0].event().unwrap();
+    assert_eq!(event.logger.as_deref(), Some("quiet"));
+    // the rate does not end up in the data of the event
+    assert!(event.extra.is_empty());
+
+    let discarded = client.discarded_events();
+    assert_eq!(discarded.len(), 1);
+    assert_eq!(discarded[0].reason, DiscardReason::SampleRate);
+    assert_eq!(discarded[0].quantity, 2);
+}
@@ -0,0 +1,58 @@
+#![cfg(feature = "with_client_implementation")]
+
+use std::env;
+use std::fs;
+use std::sync::Arc;
+
+use sentry::internals::{apply_defaults, Uuid};
+use sentry::protocol::{Envelope, Event};
+use sentry::transports::FileTransport;
+
+fn event(message: &str) -> Event<'static> {
+    Event {
+        message: Some(message.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_file_transport_envelopes() {
+    let path = env::temp_dir().join(format!("sentry-{}.envelopes", Uuid::new_v4()));
+    let transport = Arc::new(FileTransport::new(&path).unwrap());
+    let client = sentry::Client::from(sentry::ClientOptions {
+        dsn: Some("https://public@example.com/1".parse().unwrap()),
+        transport: Some(Arc::new(transport)),
+        ..Default::default()
+    });
+    client.capture_event(event("Hello World!"), None);
+    assert!(client.flush(None));
+
+    let envelope = Envelope::from_slice(&fs::read(&path).unwrap()).unwrap();
+    fs::remove_file(&path).ok();
+    let event = envelope.event().expect("expected an event");
+    assert_eq!(event.message.as_deref(), Some("Hello World!"));
+}
+
+#[test]
+fn test_file_transport_from_env() {
+    let path = env::temp_dir().join(format!("sentry-{}.jsonl", Uuid::new_v4()));
+    env::set_var("SENTRY_TRANSPORT", format!("file://{}", path.display()));
+    let client = sentry::Client::from(apply_defaults(Default::default()));
+    env::remove_var("SENTRY_TRANSPORT");
+
+    // the client is enabled without a DSN being configured
+    assert!(client.is_enabled());
+    client.capture_event(event("first"), None);
+    client.capture_event(event("second"), None);
+    assert!(client.flush(None));
+
+    let contents = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).ok();
+    let messages: Vec<_> = contents
+        .lines()
+        .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+        .filter(|line| line["type"] == "event")
+        .map(|line| line["payload"]["message"].as_str().unwrap().to_string())
+        .collect();
+    assert_eq!(messages, vec!["first", "second"]);
+}
@@ -0,0 +1,87 @@
+#![cfg(all(feature = "with_test_support", feature = "with_reqwest_transport"))]
+
+use std::time::Duration;
+
+use sentry::internals::Transport;
+use sentry::protocol::{DiscardReason, Event};
+use sentry::test::{MockResponse, MockServer};
+use sentry::transports::ReqwestHttpTransport;
+
+fn event(message: &str) -> Event<'static> {
+    Event {
+        message: Some(message.to_string()),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_mock_server_records_requests() {
+    let server = MockServer::start();
+    let transport = ReqwestHttpTransport::new(&sentry::ClientOptions {
+        dsn: Some(server.dsn()),
+        ..Default::default()
+    });
+    transport.send_envelope(event("Hello World!").into());
+    assert!(transport.flush(Duration::from_secs(5)));
+
+    let requests = server.fetch_and_clear_requests();
+    assert_eq!(requests.len(), 1);
+    let request = &requests[0];
+    assert_eq!(request.method, "POST");
+    assert_eq!(request.path, "/api/1/envelope/");
+    assert_eq!(request.auth().unwrap().public_key(), "public");
+    assert_eq!(request.content_encoding(), Some("gzip"));
+
+    let envelope = request.envelope().unwrap();
+    assert_eq!(
+        envelope.event().unwrap().message.as_deref(),
+        Some("Hello World!")
+    );
+}
+
+#[test]
+fn test_mock_server_rate_limit() {
+    let server = MockServer::start();
+    server.respond_with(MockResponse::rate_limited(Duration::from_secs(60)));
+    let transport = ReqwestHttpTransport::new(&sentry::ClientOptions {
+        dsn: Some(server.dsn()),
+        ..Default::default()
+    });
+
+    transport.send_envelope(event("first").into());
+    assert!(server.wait_for_requests(1, Duration::from_secs(5)));
+    assert!(transport.flush(Duration::from_secs(5)));
+
+    // the second event is dropped by the rate limiter of the transport
+    transport.send_envelope(event("second").into());
+    assert!(transport.flush(Duration::from_secs(5)));
+    assert_eq!(server.requests().len(), 1);
+
+    // both the rejected and the dropped event are reported
+    let discarded = transport.client_reports().unwrap().discarded_events();
+    assert_eq!(discarded.len(), 1);
+    assert_eq!(discarded[0].reason, DiscardReason::RatelimitBackoff);
+    assert_eq!(discarded[0].quantity, 2);
+}
+
+#[test]
+fn test_mock_server_error() {
+    let server = MockServer::start();
+    server.set_default_response(MockResponse::new(500));
+    let transport = ReqwestHttpTransport::new(&sentry::ClientOptions {
+        dsn: Some(server.dsn()),
+        retry_policy: sentry::RetryPolicy {
+            base_delay: Duration::from_millis(10),
+            ..Default::default()
+        },
+        ..Default::default()
+    });
+
+    transport.send_envelope(event("Hello World!").into());
+    assert!(transport.flush(Duration::from_secs(5)));
+
+    // the envelope is retried until the policy gives up
+    assert_eq!(server.requests().len(), 3);
+    let discarded = transport.client_reports().unwrap().discarded_events();
+    assert_eq!(discarded[0].reason, DiscardReason::NetworkError);
+}
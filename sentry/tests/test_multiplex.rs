@@ -0,0 +1,148 @@
+#![cfg(feature = "with_test_support")]
+
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use sentry::internals::{ClientReportRecorder, Dsn, Transport};
+use sentry::protocol::{DataCategory, DiscardReason, Envelope, Event, Transaction};
+use sentry::test::TestTransport;
+use sentry::transports::{MultiplexTransport, MultiplexTransportFactory};
+
+/// A factory handing out the given transport and recording the DSN it was
+/// created for.
+fn factory(
+    transport: Arc<TestTransport>,
+    dsn: Arc<Mutex<Option<Dsn>>>,
+) -> impl Fn(&sentry::ClientOptions) -> Arc<dyn Transport> + Clone + Send + Sync + 'static {
+    move |options| {
+        *dsn.lock().unwrap() = options.dsn.clone();
+        transport.clone()
+    }
+}
+
+#[test]
+fn test_multiplex() {
+    let primary: Dsn = "https://public@primary.example.com/1".parse().unwrap();
+    let legacy: Dsn = "https://public@legacy.example.com/2".parse().unwrap();
+    let primary_transport = TestTransport::new();
+    let legacy_transport = TestTransport::new();
+    let primary_dsn = Arc::new(Mutex::new(None));
+    let legacy_dsn = Arc::new(Mutex::new(None));
+
+    let multiplex = MultiplexTransportFactory::new()
+        .add_destination(
+            primary.clone(),
+            factory(primary_transport.clone(), primary_dsn.clone()),
+        )
+        .add_filtered_destination(
+            legacy.clone(),
+            factory(legacy_transport.clone(), legacy_dsn.clone()),
+            |item| item.type_name() == "event",
+        );
+    let client = sentry::Client::from(sentry::ClientOptions {
+        dsn: Some(primary.clone()),
+        transport: Some(Arc::new(multiplex)),
+        ..Default::default()
+    });
+
+    // every destination gets a transport of its own
+    assert_eq!(*primary_dsn.lock().unwrap(), Some(primary));
+    assert_eq!(*legacy_dsn.lock().unwrap(), Some(legacy));
+
+    client.capture_event(Event::default(), None);
+    client.send_envelope(Transaction::default().into());
+
+    let primary_envelopes = primary_transport.fetch_and_clear_envelopes();
+    let legacy_envelopes = legacy_transport.fetch_and_clear_envelopes();
+    assert_eq!(primary_envelopes.len(), 2);
+    assert_eq!(legacy_envelopes.len(), 1);
+    assert!(legacy_envelopes[0].event().is_some());
+}
+
+#[test]
+fn test_multiplex_spool_dirs() {
+    let primary: Dsn = "https://public@primary.example.com/1".parse().unwrap();
+    let legacy: Dsn = "https://public@legacy.example.com/2".parse().unwrap();
+    let primary_dirs = Arc::new(Mutex::new(vec![]));
+    let legacy_dirs = Arc::new(Mutex::new(vec![]));
+    let spool_dir_factory = |dirs: Arc<Mutex<Vec<Option<PathBuf>>>>| {
+        move |options: &sentry::ClientOptions| -> Arc<dyn Transport> {
+            dirs.lock().unwrap().push(options.spool_dir.clone());
+            TestTransport::new()
+        }
+    };
+
+    let factory = MultiplexTransportFactory::new()
+        .add_destination(primary.clone(), spool_dir_factory(primary_dirs.clone()))
+        .add_destination(legacy, spool_dir_factory(legacy_dirs.clone()));
+    let base = PathBuf::from("/tmp/sentry-spool");
+    for spool_dir in &[None, Some(base.clone())] {
+        MultiplexTransport::new(
+            &sentry::ClientOptions {
+                dsn: Some(primary.clone()),
+                spool_dir: spool_dir.clone(),
+                ..Default::default()
+            },
+            &factory,
+        );
+    }
+
+    // spooling stays disabled if it was, otherwise every destination gets a
+    // directory of its own
+    let primary_dirs = primary_dirs.lock().unwrap();
+    let legacy_dirs = legacy_dirs.lock().unwrap();
+    assert_eq!(primary_dirs[0], None);
+    assert_eq!(legacy_dirs[0], None);
+    let primary_dir = primary_dirs[1].as_ref().unwrap();
+    let legacy_dir = legacy_dirs[1].as_ref().unwrap();
+    assert_ne!(primary_dir, legacy_dir);
+    assert_eq!(primary_dir.parent(), Some(base.as_path()));
+    assert_eq!(legacy_dir.parent(), Some(base.as_path()));
+}
+
+/// A test transport that discards data on its own.
+struct ReportingTransport {
+    inner: Arc<TestTransport>,
+    reports: Arc<ClientReportRecorder>,
+}
+
+impl Transport for ReportingTransport {
+    fn send_event(&self, event: Event<'static>) {
+        self.send_envelope(event.into())
+    }
+
+    fn send_envelope(&self, envelope: Envelope) {
+        self.inner.send_envelope(envelope);
+    }
+
+    fn client_reports(&self) -> Option<Arc<ClientReportRecorder>> {
+        Some(self.reports.clone())
+    }
+}
+
+#[test]
+fn test_multiplex_client_reports() {
+    for &send_client_reports in &[false, true] {
+        let dsn: Dsn = "https://public@primary.example.com/1".parse().unwrap();
+        let transport = TestTransport::new();
+        let reports = Arc::new(ClientReportRecorder::new());
+        let destination = Arc::new(Arc::new(ReportingTransport {
+            inner: transport.clone(),
+            reports: reports.clone(),
+        }));
+        let factory = MultiplexTransportFactory::new().add_destination(dsn.clone(), destination);
+        let options = sentry::ClientOptions {
+            dsn: Some(dsn),
+            send_client_reports,
+            ..Default::default()
+        };
+        let multiplex = MultiplexTransport::new(&options, &factory);
+
+        reports.record(DiscardReason::NetworkError, DataCategory::Error, 1);
+        assert!(multiplex.flush(Duration::from_secs(1)));
+
+        let envelopes = transport.fetch_and_clear_envelopes();
+        assert_eq!(envelopes.len(), send_client_reports as usize);
+    }
+}
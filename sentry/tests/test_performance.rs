@@ -0,0 +1,170 @@
+#![cfg(feature = "with_test_support")]
+
+use std::sync::Arc;
+
+use sentry::protocol::{Context, DataCategory, DiscardReason, EnvelopeItem, SpanStatus};
+
+#[test]
+fn test_transaction_with_spans() {
+    let envelopes = sentry::test::with_captured_envelopes_options(
+        || {
+            let transaction =
+                sentry::start_transaction(sentry::TransactionContext::new("batch", "task"));
+            transaction.set_tag("worker", "worker1");
+
+            let span = transaction.start_child("db", "SELECT 1");
+            let nested = span.start_child("db.fetch", "fetch rows");
+            nested.finish();
+            span.set_status(SpanStatus::Ok);
+            span.finish();
+
+            // finished after the transaction, never sent
+            let late = transaction.start_child("late", "too late");
+            transaction.clone().finish();
+            late.finish();
+            transaction.finish();
+        },
+        sentry::ClientOptions {
+            release: Some("app@1.0".into()),
+            traces_sample_rate: 1.0,
+            ..Default::default()
+        },
+    );
+    assert_eq!(envelopes.len(), 1);
+
+    let transaction = match envelopes[0].items().next().unwrap() {
+        EnvelopeItem::Transaction(transaction) => transaction.clone(),
+        item => panic!("unexpected item {:?}", item),
+    };
+    assert_eq!(envelopes[0].uuid(), Some(&transaction.event_id));
+    assert_eq!(transaction.name.as_deref(), Some("batch"));
+    assert_eq!(transaction.release.as_deref(), Some("app@1.0"));
+    assert_eq!(transaction.tags["worker"], "worker1");
+    assert!(transaction.timestamp.is_some());
+
+    let trace = match transaction.contexts["trace"] {
+        Context::Trace(ref trace) => trace.clone(),
+        ref context => panic!("unexpected context {:?}", context),
+    };
+    assert_eq!(trace.op.as_deref(), Some("task"));
+
+    assert_eq!(transaction.spans.len(), 2);
+    let (nested, span) = (&transaction.spans[0], &transaction.spans[1]);
+    assert_eq!(span.trace_id, trace.trace_id);
+    assert_eq!(span.parent_span_id, Some(trace.span_id));
+    assert_eq!(span.status, Some(SpanStatus::Ok));
+    assert_eq!(nested.trace_id, trace.trace_id);
+    assert_eq!(nested.parent_span_id, Some(span.span_id));
+    assert_eq!(nested.op.as_deref(), Some("db.fetch"));
+}
+
+#[test]
+fn test_transaction_sampling() {
+    let envelopes = sentry::test::with_captured_envelopes(|| {
+        let transaction = sentry::start_transaction(sentry::TransactionContext::new("a", "b"));
+        assert!(!transaction.is_sampled());
+        transaction.start_child("c", "d").finish();
+        transaction.finish();
+    });
+    // only the client report about the unsampled transaction is sent
+    assert_eq!(envelopes.len(), 1);
+    match envelopes[0].items().next().unwrap() {
+        EnvelopeItem::ClientReport(report) => {
+            let discarded = &report.discarded_events[0];
+            assert_eq!(discarded.reason, DiscardReason::SampleRate);
+            assert_eq!(discarded.category, DataCategory::Transaction);
+            assert_eq!(discarded.quantity, 1);
+        }
+        item => panic!("unexpected item {:?}", item),
+    }
+
+    let envelopes = sentry::test::with_captured_envelopes_options(
+        || {
+            sentry::start_transaction(sentry::TransactionContext::new("keep", "op")).finish();
+            sentry::start_transaction(sentry::TransactionContext::new("drop", "op")).finish();
+
+            let mut ctx = sentry::TransactionContext::new("drop", "op");
+            ctx.set_sampled(Some(true));
+            sentry::start_transaction(ctx).finish();
+        },
+        sentry::ClientOptions {
+            traces_sampler: Some(Arc::new(|ctx| {
+                assert_eq!(ctx.operation(), "op");
+                if ctx.name() == "keep" {
+                    1.0
+                } else {
+                    0.0
+                }
+            })),
+            send_client_reports: false,
+            ..Default::default()
+        },
+    );
+    assert_eq!(envelopes.len(), 2);
+}
+
+#[test]
+fn test_ignore_transactions() {
+    let envelopes = sentry::test::with_captured_envelopes_options(
+        || {
+            let transaction =
+                sentry::start_transaction(sentry::TransactionContext::new("GET /health", "http"));
+            assert!(!transaction.is_sampled());
+            transaction.finish();
+            sentry::start_transaction(sentry::TransactionContext::new("GET /users", "http"))
+                .finish();
+        },
+        sentry::ClientOptions {
+            traces_sample_rate: 1.0,
+            ignore_transactions: vec!["/health".into()],
+            ..Default::default()
+        },
+    );
+    assert_eq!(envelopes.len(), 2);
+    match envelopes[0].items().next() {
+        Some(EnvelopeItem::Transaction(transaction)) => {
+            assert_eq!(transaction.name.as_deref(), Some("GET /users"))
+        }
+        item => panic!("unexpected item {:?}", item),
+    }
+    let report = envelopes[1].items().next();
+    match report {
+        Some(EnvelopeItem::ClientReport(report)) => {
+            let discarded = &report.discarded_events[0];
+            assert_eq!(discarded.reason, DiscardReason::Filtered);
+            assert_eq!(discarded.category, DataCategory::Transaction);
+        }
+        item => panic!("unexpected item {:?}", item),
+    };
+}
+
+#[test]
+fn test_event_trace_context() {
+    let mut trace_context = None;
+    let events = sentry::test::with_captured_events(|| {
+        let transaction = sentry::start_transaction(sentry::TransactionContext::new("a", "b"));
+        let span = transaction.start_child("c", "d");
+        trace_context = Some(span.get_trace_context());
+        sentry::configure_scope(|scope| scope.set_span(Some(span.into())));
+        sentry::capture_message("Hello World!", sentry::Level::Warning);
+    });
+    assert_eq!(events.len(), 1);
+
+    let trace_context = trace_context.unwrap();
+    match events[0].contexts["trace"] {
+        Context::Trace(ref trace) => {
+            assert_eq!(trace.trace_id, trace_context.trace_id);
+            assert_eq!(trace.span_id, trace_context.span_id);
+        }
+        ref context => panic!("unexpected context {:?}", context),
+    }
+}
+
+#[test]
+fn test_continue_from_span() {
+    let transaction = sentry::start_transaction(sentry::TransactionContext::new("a", "b"));
+    let span: sentry::TransactionOrSpan = transaction.start_child("c", "d").into();
+    let ctx = sentry::TransactionContext::continue_from_span("e", "f", Some(&span));
+    assert_eq!(ctx.trace_id(), span.get_trace_context().trace_id);
+    assert_eq!(ctx.sampled(), Some(false));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "with_reqwest_transport")]
+
+use std::net::TcpListener;
+
+use sentry::internals::{Dsn, Transport};
+use sentry::protocol::{DiscardReason, Event};
+use sentry::transports::ReqwestHttpTransport;
+use sentry::QueueOverflow;
+
+#[test]
+fn test_queue_overflow() {
+    // nothing ever answers, so the first envelope blocks the send loop
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let dsn: Dsn = format!(
+        "http://public@127.0.0.1:{}/1",
+        listener.local_addr().unwrap().port()
+    )
+    .parse()
+    .unwrap();
+
+    for overflow in &[QueueOverflow::DropNewest, QueueOverflow::DropOldest] {
+        let transport = ReqwestHttpTransport::new(&sentry::ClientOptions {
+            dsn: Some(dsn.clone()),
+            queue_capacity: 1,
+            queue_overflow: *overflow,
+            ..Default::default()
+        });
+        for _ in 0..10 {
+            transport.send_envelope(Event::default().into());
+        }
+
+        // at most one envelope is in flight and one queued
+        let overflows = transport.queue_overflows();
+        assert!(overflows >= 8);
+        let discarded = transport.client_reports().unwrap().discarded_events();
+        assert_eq!(discarded[0].reason, DiscardReason::QueueOverflow);
+        assert_eq!(discarded[0].quantity, overflows);
+    }
+}
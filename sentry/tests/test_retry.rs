@@ -0,0 +1,107 @@
+#![cfg(all(feature = "with_test_support", feature = "with_reqwest_transport"))]
+
+use std::time::{Duration, Instant};
+
+use sentry::internals::{Dsn, Transport};
+use sentry::protocol::{DiscardReason, Event};
+use sentry::test::{MockResponse, MockServer};
+use sentry::transports::ReqwestHttpTransport;
+use sentry::RetryPolicy;
+
+/// Starts a server answering requests with the given status codes,
+/// repeating the last one.
+fn serve(statuses: &[u16]) -> MockServer {
+    let server = MockServer::start();
+    let (last, scripted) = statuses.split_last().unwrap();
+    for status in scripted {
+        server.respond_with(MockResponse::new(*status));
+    }
+    server.set_default_response(MockResponse::new(*last));
+    server
+}
+
+fn options(dsn: Dsn, retry_policy: RetryPolicy) -> sentry::ClientOptions {
+    sentry::ClientOptions {
+        dsn: Some(dsn),
+        retry_policy,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_retry_transient_failure() {
+    let server = serve(&[503, 200]);
+    let transport = ReqwestHttpTransport::new(&options(
+        server.dsn(),
+        RetryPolicy {
+            base_delay: Duration::from_millis(10),
+            ..Default::default()
+        },
+    ));
+    transport.send_envelope(Event::default().into());
+    assert!(transport.flush(Duration::from_secs(5)));
+
+    assert_eq!(server.requests().len(), 2);
+    assert!(transport
+        .client_reports()
+        .unwrap()
+        .discarded_events()
+        .is_empty());
+}
+
+#[test]
+fn test_retry_max_attempts() {
+    let server = serve(&[500, 501]);
+    let transport = ReqwestHttpTransport::new(&options(
+        server.dsn(),
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_millis(10),
+            ..Default::default()
+        },
+    ));
+
+    // 501 is not retryable
+    transport.send_envelope(Event::default().into());
+    assert!(transport.flush(Duration::from_secs(5)));
+    assert_eq!(server.requests().len(), 2);
+
+    let discarded = transport.client_reports().unwrap().discarded_events();
+    assert_eq!(discarded.len(), 1);
+    assert_eq!(discarded[0].reason, DiscardReason::NetworkError);
+}
+
+#[test]
+fn test_retry_does_not_block_shutdown() {
+    let server = serve(&[503]);
+    let transport = ReqwestHttpTransport::new(&options(
+        server.dsn(),
+        RetryPolicy {
+            base_delay: Duration::from_secs(60),
+            jitter: 0.0,
+            ..Default::default()
+        },
+    ));
+    transport.send_envelope(Event::default().into());
+    assert!(server.wait_for_requests(1, Duration::from_secs(5)));
+
+    let start = Instant::now();
+    assert!(transport.shutdown(Duration::from_secs(5)));
+    assert!(start.elapsed() < Duration::from_secs(5));
+}
+
+#[test]
+fn test_retry_delay() {
+    let policy = RetryPolicy::default();
+    assert_eq!(policy.delay(1), Duration::from_millis(500));
+    assert_eq!(policy.delay(3), Duration::from_secs(2));
+    // absurd delays are capped instead of overflowing
+    assert_eq!(policy.delay(100), Duration::from_secs(60 * 60));
+    // `u64::MAX` is not available on all supported compilers
+    #[allow(clippy::legacy_numeric_constants)]
+    let policy = RetryPolicy {
+        base_delay: Duration::from_secs(std::u64::MAX),
+        ..Default::default()
+    };
+    assert_eq!(policy.delay(2), Duration::from_secs(60 * 60));
+}
@@ -0,0 +1,167 @@
+#![cfg(feature = "with_test_support")]
+
+use std::sync::Arc;
+
+use sentry::protocol::{
+    EnvelopeItem, Event, Exception, Level, Mechanism, SessionStatus, SessionUpdate,
+};
+
+fn options() -> sentry::ClientOptions {
+    sentry::ClientOptions {
+        release: Some("app@1.0".into()),
+        ..Default::default()
+    }
+}
+
+fn sessions(envelope: &sentry::protocol::Envelope) -> Vec<SessionUpdate<'static>> {
+    envelope
+        .items()
+        .filter_map(|item| match item {
+            EnvelopeItem::SessionUpdate(session) => Some(session.clone()),
+            _ => None,
+        })
+        .collect()
+}
+
+#[test]
+fn test_session_exited() {
+    let envelopes = sentry::test::with_captured_envelopes_options(
+        || {
+            sentry::start_session();
+            sentry::capture_message("just info", Level::Info);
+            sentry::end_session();
+        },
+        options(),
+    );
+    assert_eq!(envelopes.len(), 2);
+
+    // the info message does not change the session but carries its start
+    assert!(envelopes[0].event().is_some());
+    let session = &sessions(&envelopes[0])[0];
+    assert_eq!(session.status, SessionStatus::Ok);
+    assert!(session.init);
+    assert_eq!(session.attributes.release, "app@1.0");
+
+    let session = &sessions(&envelopes[1])[0];
+    assert_eq!(session.status, SessionStatus::Exited);
+    assert_eq!(session.errors, 0);
+    assert!(!session.init);
+}
+
+#[test]
+fn test_session_errored() {
+    let envelopes = sentry::test::with_captured_envelopes_options(
+        || {
+            sentry::configure_scope(|scope| {
+                scope.set_user(Some(sentry::User {
+                    id: Some("user-1".into()),
+                    ..Default::default()
+                }))
+            });
+            sentry::start_session();
+            sentry::capture_message("first", Level::Error);
+            sentry::capture_message("second", Level::Error);
+            sentry::end_session();
+        },
+        options(),
+    );
+    assert_eq!(envelopes.len(), 3);
+
+    let session = &sessions(&envelopes[0])[0];
+    assert!(envelopes[0].event().is_some());
+    assert_eq!(session.status, SessionStatus::Errored);
+    assert_eq!(session.errors, 1);
+    assert_eq!(session.distinct_id.as_deref(), Some("user-1"));
+    assert!(session.init);
+
+    let session = &sessions(&envelopes[1])[0];
+    assert_eq!(session.errors, 2);
+    assert!(!session.init);
+
+    let session = &sessions(&envelopes[2])[0];
+    assert_eq!(session.status, SessionStatus::Errored);
+    assert_eq!(session.errors, 2);
+}
+
+#[test]
+fn test_session_crashed() {
+    let envelopes = sentry::test::with_captured_envelopes_options(
+        || {
+            sentry::start_session();
+            sentry::capture_event(Event {
+                exception: vec![Exception {
+                    ty: "panic".into(),
+                    mechanism: Some(Mechanism {
+                        ty: "panic".into(),
+                        handled: Some(false),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                }]
+                .into(),
+                level: Level::Fatal,
+                ..Default::default()
+            });
+            // a crashed session is final
+            sentry::capture_message("after the crash", Level::Error);
+            sentry::end_session();
+        },
+        options(),
+    );
+    assert_eq!(envelopes.len(), 3);
+
+    let session = &sessions(&envelopes[0])[0];
+    assert_eq!(session.status, SessionStatus::Crashed);
+    assert_eq!(session.errors, 1);
+    assert!(sessions(&envelopes[1]).is_empty());
+
+    let session = &sessions(&envelopes[2])[0];
+    assert_eq!(session.status, SessionStatus::Crashed);
+    assert_eq!(session.errors, 1);
+}
+
+#[test]
+fn test_session_requires_release() {
+    let envelopes = sentry::test::with_captured_envelopes(|| {
+        sentry::start_session();
+        sentry::capture_message("an error", Level::Error);
+        sentry::end_session();
+    });
+    assert_eq!(envelopes.len(), 1);
+    assert!(sessions(&envelopes[0]).is_empty());
+}
+
+#[test]
+fn test_session_aggregates() {
+    let envelopes = sentry::test::with_captured_envelopes_options(
+        || {
+            for idx in 0..4 {
+                let hub = Arc::new(sentry::Hub::new_from_top(sentry::Hub::current()));
+                sentry::Hub::run(hub, || {
+                    sentry::start_session();
+                    if idx == 0 {
+                        sentry::capture_message("an error", Level::Error);
+                    }
+                    sentry::end_session();
+                });
+            }
+        },
+        sentry::ClientOptions {
+            session_mode: sentry::SessionMode::Request,
+            ..options()
+        },
+    );
+
+    // the error event is sent without a session update
+    assert_eq!(envelopes.len(), 2);
+    assert!(sessions(&envelopes[0]).is_empty());
+
+    let aggregates = match envelopes[1].items().next().unwrap() {
+        EnvelopeItem::SessionAggregates(aggregates) => aggregates.clone(),
+        item => panic!("unexpected item {:?}", item),
+    };
+    assert_eq!(aggregates.attributes.release, "app@1.0");
+    let exited: u32 = aggregates.aggregates.iter().map(|x| x.exited).sum();
+    let errored: u32 = aggregates.aggregates.iter().map(|x| x.errored).sum();
+    assert_eq!((exited, errored), (3, 1));
+}
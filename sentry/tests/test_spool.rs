@@ -0,0 +1,102 @@
+#![cfg(all(feature = "with_test_support", feature = "with_reqwest_transport"))]
+
+use std::fs;
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use sentry::internals::{Dsn, Transport, Uuid};
+use sentry::protocol::{Envelope, Event};
+use sentry::test::MockServer;
+use sentry::transports::ReqwestHttpTransport;
+
+fn spool_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("sentry-spool-{}-{}", name, Uuid::new_v4()));
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+fn spooled_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().map_or(false, |ext| ext == "envelope"))
+        .collect();
+    files.sort();
+    files
+}
+
+fn options(dsn: Dsn, dir: &Path) -> sentry::ClientOptions {
+    sentry::ClientOptions {
+        dsn: Some(dsn),
+        spool_dir: Some(dir.to_path_buf()),
+        ..Default::default()
+    }
+}
+
+/// Returns a DSN nothing listens on.
+fn unreachable_dsn() -> Dsn {
+    let port = TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap()
+        .port();
+    format!("http://public@127.0.0.1:{}/1", port)
+        .parse()
+        .unwrap()
+}
+
+#[test]
+fn test_spool_and_replay() {
+    let dir = spool_dir("replay");
+    let event = Event::default();
+    let event_id = event.event_id;
+
+    let transport = ReqwestHttpTransport::new(&options(unreachable_dsn(), &dir));
+    transport.send_envelope(event.into());
+    transport.shutdown(Duration::from_secs(5));
+    drop(transport);
+    assert_eq!(spooled_files(&dir).len(), 1);
+
+    // the spool is replayed when the next transport starts
+    let server = MockServer::start();
+    let _transport = ReqwestHttpTransport::new(&options(server.dsn(), &dir));
+    assert!(server.wait_for_requests(1, Duration::from_secs(5)));
+    let envelope = server.requests()[0].envelope().unwrap();
+    assert_eq!(envelope.event().unwrap().event_id, event_id);
+
+    for _ in 0..50 {
+        if spooled_files(&dir).is_empty() {
+            break;
+        }
+        thread::sleep(Duration::from_millis(100));
+    }
+    assert!(spooled_files(&dir).is_empty());
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn test_spool_max_size() {
+    let dir = spool_dir("max-size");
+    let events: Vec<_> = (0..3).map(|_| Event::default()).collect();
+    let last_id = events[2].event_id;
+    let envelope_size = Envelope::from(events[0].clone()).to_vec().len();
+
+    let transport = ReqwestHttpTransport::new(&sentry::ClientOptions {
+        spool_max_size: (envelope_size + envelope_size / 2) as u64,
+        ..options(unreachable_dsn(), &dir)
+    });
+    for event in events {
+        transport.send_envelope(event.into());
+    }
+    transport.shutdown(Duration::from_secs(5));
+    drop(transport);
+
+    // only the newest envelope fits into the spool
+    let files = spooled_files(&dir);
+    assert_eq!(files.len(), 1);
+    let envelope = Envelope::from_slice(&fs::read(&files[0]).unwrap()).unwrap();
+    assert_eq!(envelope.event().unwrap().event_id, last_id);
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,64 @@
+#![cfg(feature = "with_test_support")]
+
+#[test]
+fn test_spawn_keeps_scope() {
+    let events = sentry::test::with_captured_events(|| {
+        sentry::configure_scope(|scope| scope.set_tag("worker", "worker1"));
+        sentry::add_breadcrumb(sentry::Breadcrumb {
+            message: Some("Spawning thread".into()),
+            ..Default::default()
+        });
+
+        sentry::thread::spawn(|| {
+            sentry::configure_scope(|scope| scope.set_tag("thread", "spawned"));
+            sentry::capture_message("From thread", sentry::Level::Info);
+        })
+        .join()
+        .unwrap();
+
+        sentry::thread::Builder::new()
+            .name("worker".into())
+            .spawn(|| sentry::capture_message("From builder", sentry::Level::Info))
+            .unwrap()
+            .join()
+            .unwrap();
+
+        // the spawned threads do not modify the scope of this thread
+        sentry::capture_message("From main", sentry::Level::Info);
+    });
+
+    assert_eq!(events.len(), 3);
+    for event in &events {
+        assert_eq!(event.tags["worker"], "worker1");
+        assert_eq!(
+            event.breadcrumbs[0].message.as_deref(),
+            Some("Spawning thread")
+        );
+    }
+    assert_eq!(events[0].tags["thread"], "spawned");
+    assert!(!events[1].tags.contains_key("thread"));
+    assert!(!events[2].tags.contains_key("thread"));
+}
+
+#[cfg(feature = "with_rayon")]
+#[test]
+fn test_rayon() {
+    let events = sentry::test::with_captured_events(|| {
+        sentry::configure_scope(|scope| scope.set_tag("worker", "worker1"));
+        sentry::rayon::join(
+            || sentry::capture_message("Left", sentry::Level::Info),
+            || sentry::capture_message("Right", sentry::Level::Info),
+        );
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+        sentry::rayon::spawn(move || {
+            sentry::capture_message("Spawned", sentry::Level::Info);
+            sender.send(()).unwrap();
+        });
+        receiver.recv().unwrap();
+    });
+    assert_eq!(events.len(), 3);
+    for event in &events {
+        assert_eq!(event.tags["worker"], "worker1");
+    }
+}
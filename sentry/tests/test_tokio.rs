@@ -0,0 +1,33 @@
+#![cfg(all(feature = "with_tokio", feature = "with_test_support"))]
+
+#[test]
+fn test_spawn_forks_hub() {
+    let mut events = sentry::test::with_captured_events(|| {
+        let mut runtime = tokio::runtime::Runtime::new().unwrap();
+        sentry::configure_scope(|scope| scope.set_tag("outer", "yes"));
+
+        runtime.block_on(async {
+            let tasks: Vec<_> = (0..4)
+                .map(|idx| {
+                    sentry::tokio::spawn(async move {
+                        sentry::configure_scope(|scope| scope.set_tag("task", idx));
+                        sentry::capture_message(&format!("task {}", idx), sentry::Level::Info);
+                    })
+                })
+                .collect();
+            for task in tasks {
+                task.await.unwrap();
+            }
+        });
+
+        sentry::capture_message("outside", sentry::Level::Info);
+    });
+
+    events.sort_by(|a, b| a.message.cmp(&b.message));
+    assert_eq!(events.len(), 5);
+    assert!(!events[0].tags.contains_key("task"));
+    for (idx, event) in events[1..].iter().enumerate() {
+        assert_eq!(event.tags["outer"], "yes");
+        assert_eq!(event.tags["task"], idx.to_string());
+    }
+}
@@ -0,0 +1,66 @@
+#![cfg(all(feature = "with_test_support", feature = "with_tokio_transport"))]
+
+use std::time::Duration;
+
+use sentry::internals::Transport;
+use sentry::protocol::Event;
+use sentry::test::MockServer;
+use sentry::transports::TokioHttpTransport;
+
+#[tokio::test]
+async fn test_tokio_transport() {
+    let server = MockServer::start();
+    let transport = TokioHttpTransport::new(&sentry::ClientOptions {
+        dsn: Some(server.dsn()),
+        ..Default::default()
+    });
+
+    let event = Event::default();
+    let event_id = event.event_id;
+    transport.send_envelope(event.into());
+    assert!(transport.close(Duration::from_secs(5)).await);
+
+    let requests = server.fetch_and_clear_requests();
+    assert_eq!(requests.len(), 1);
+    // bodies are gzip compressed by default
+    assert_eq!(requests[0].content_encoding(), Some("gzip"));
+    let envelope = requests[0].envelope().unwrap();
+    assert_eq!(envelope.event().unwrap().event_id, event_id);
+
+    // a closed transport does not accept new envelopes
+    transport.send_envelope(Event::default().into());
+    assert!(transport.flush(Duration::from_secs(1)).await);
+    assert_eq!(
+        transport.client_reports().unwrap().discarded_events()[0].quantity,
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_tokio_transport_never_blocks() {
+    let transport = TokioHttpTransport::new(&sentry::ClientOptions {
+        dsn: Some("http://public@127.0.0.1:1/1".parse().unwrap()),
+        queue_capacity: 1,
+        queue_overflow: sentry::QueueOverflow::Block(Duration::from_secs(5)),
+        ..Default::default()
+    });
+
+    // the send loop cannot run on this single threaded runtime before the
+    // test yields, so blocking would wait out the full timeout
+    let start = std::time::Instant::now();
+    for _ in 0..3 {
+        transport.send_envelope(Event::default().into());
+    }
+    assert!(start.elapsed() < Duration::from_secs(1));
+    assert_eq!(transport.queue_overflows(), 2);
+}
+
+#[test]
+fn test_tokio_transport_outside_runtime() {
+    let server = MockServer::start();
+    let options = sentry::ClientOptions {
+        dsn: Some(server.dsn()),
+        ..Default::default()
+    };
+    assert!(TokioHttpTransport::try_new(&options).is_err());
+}